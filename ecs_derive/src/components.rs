@@ -0,0 +1,553 @@
+
+use parse::{generic_args, replace_self, to_string, Error, Field, Fields, Input, Result};
+
+enum Kind
+{
+    Hot,
+    Cold,
+    HotIndexed,
+    ColdIndexed,
+}
+
+impl Kind
+{
+    /// The `ComponentList` constructor for this kind of storage.
+    fn constructor(&self) -> &'static str
+    {
+        match *self
+        {
+            Kind::Hot => "hot",
+            Kind::Cold => "cold",
+            Kind::HotIndexed => "hot_indexed",
+            Kind::ColdIndexed => "cold_indexed",
+        }
+    }
+}
+
+struct List<'a>
+{
+    field: &'a Field,
+    kind: Kind,
+    /// The component type, with any `Self` replaced by the manager's type.
+    component: String,
+    replicated: bool,
+}
+
+/// A `#[plugin]` field holding a component group.
+struct Group<'a>
+{
+    field: &'a Field,
+    ty: String,
+}
+
+pub fn derive(input: &Input) -> Result<String>
+{
+    let (lists, groups, dynamic) = members(input, "ComponentManager")?;
+    let bounds: Vec<String> = groups.iter()
+        .map(|g| format!("for<'__ecs> {}: ::ecs::DiffGroup<{}>", g.ty, input.ty()))
+        .collect();
+
+    let mut code = format!("
+        impl{impl_params} ::ecs::ComponentManager for {ty} {where_clause}
+        {{
+            {lifecycle}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        where_clause = input.where_clause(&[]),
+        lifecycle = lifecycle(input, &lists, &groups, dynamic, "Self"),
+    );
+    code.push_str(&diff(input, &lists, &groups, "DiffComponents", &bounds));
+    let bounds: Vec<String> = groups.iter()
+        .map(|g| format!("for<'__ecs> {}: ::ecs::CloneGroup<{}>", g.ty, input.ty()))
+        .collect();
+    code.push_str(&clone(input, &lists, &groups, "CloneComponents", "Self", &bounds));
+    if let Some(attr) = input.attr("builder") {
+        code.push_str(&builder(input, &lists, &attr.ident_arg()?.to_string()));
+    }
+    if let Some(attr) = input.attr("modifier") {
+        code.push_str(&modifier(input, &lists, &attr.ident_arg()?.to_string()));
+    }
+    code.push_str(&has_component(input, &lists));
+    code.push_str(&has_group(input, &groups));
+    if lists.iter().any(|l| l.replicated) {
+        code.push_str(&replicated(input, &lists));
+    }
+    Ok(code)
+}
+
+/// An empty `ComponentManager` impl, given when the real one can't be derived.
+pub fn stub(input: &Input) -> String
+{
+    format!("
+        impl{impl_params} ::ecs::ComponentManager for {ty} {where_clause}
+        {{
+            fn __new() -> Self {{ unreachable!() }}
+            fn __remove_all(&mut self, _: &::ecs::IndexedEntity<Self>) {{}}
+            fn __move_entity(&mut self, _: &mut Self, _: &::ecs::IndexedEntity<Self>, _: &::ecs::IndexedEntity<Self>) {{}}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        where_clause = input.where_clause(&[]),
+    )
+}
+
+pub fn derive_group(input: &Input) -> Result<String>
+{
+    let (lists, _, _) = members(input, "ComponentGroup")?;
+    // The manager is whatever the lists are declared with, which has to be a type parameter.
+    let manager = match lists.first().and_then(|l| generic_args(&l.field.ty, "ComponentList"))
+    {
+        Some(ref args) if input.generics.mentions_type_param(args[0].iter().cloned().collect()) => to_string(&args[0]),
+        _ => return Err(Error::new(input.name.span(),
+            "component groups need component lists generic over the manager, like `ComponentList<C, T>`")),
+    };
+    if let Some(l) = lists.iter().find(|l| generic_args(&l.field.ty, "ComponentList").map(|a| to_string(&a[0])) != Some(manager.clone())) {
+        return Err(Error::new(l.field.ty_span(), format!("expected `ComponentList<{}, T>` for `{}`", manager, l.field.name)));
+    }
+    let bounds = vec![format!("{}: ::ecs::ComponentManager", manager)];
+
+    let mut code = format!("
+        impl{impl_params} ::ecs::ComponentGroup<{manager}> for {ty} {where_clause}
+        {{
+            {lifecycle}
+        }}",
+        impl_params = input.generics.impl_params(),
+        manager = manager,
+        ty = input.ty(),
+        where_clause = input.where_clause(&bounds),
+        lifecycle = lifecycle(input, &lists, &[], None, &manager),
+    );
+    code.push_str(&diff(input, &lists, &[], &format!("DiffGroup<{}>", manager), &bounds));
+    code.push_str(&clone(input, &lists, &[], &format!("CloneGroup<{}>", manager), &manager, &bounds));
+    Ok(code)
+}
+
+/// Sorts the fields into component lists, groups and the registry of dynamic components.
+fn members<'a>(input: &'a Input, derive: &str) -> Result<(Vec<List<'a>>, Vec<Group<'a>>, Option<&'a Field>)>
+{
+    let fields = match input.fields
+    {
+        Fields::Named(ref fields) => &fields[..],
+        Fields::Unit => &[],
+        Fields::Tuple(span) | Fields::Other(span) =>
+            return Err(Error::new(span, format!("`{}` can only be derived for structs with named fields", derive))),
+    };
+    let mut lists = Vec::new();
+    let mut groups = Vec::new();
+    let mut dynamic = None;
+    for field in fields {
+        if let Some(attr) = field.attr("dynamic") {
+            if derive == "ComponentGroup" {
+                return Err(Error::new(attr.span, "component groups can't hold dynamic components"));
+            }
+            if dynamic.is_some() {
+                return Err(Error::new(attr.span, "only one field can hold dynamic components"));
+            }
+            dynamic = Some(field);
+            continue;
+        }
+        match field.attr("plugin")
+        {
+            Some(attr) if derive == "ComponentGroup" =>
+                return Err(Error::new(attr.span, "component groups can't hold other groups")),
+            Some(_) => groups.push(Group { field, ty: replace_self(&field.ty, &input.ty()) }),
+            None => lists.push(list(input, field)?),
+        }
+    }
+    Ok((lists, groups, dynamic))
+}
+
+fn list<'a>(input: &Input, field: &'a Field) -> Result<List<'a>>
+{
+    let kind = match field.attr("storage")
+    {
+        Some(attr) => match attr.ident_arg().map(|i| i.to_string()).as_ref().map(|s| &s[..])
+        {
+            Ok("hot") => Kind::Hot,
+            Ok("cold") => Kind::Cold,
+            Ok("hot_indexed") => Kind::HotIndexed,
+            Ok("cold_indexed") => Kind::ColdIndexed,
+            _ => return Err(Error::new(attr.span, "expected `#[storage(hot)]`, `#[storage(cold)]`, `#[storage(hot_indexed)]` or `#[storage(cold_indexed)]`")),
+        },
+        None => return Err(Error::new(field.name.span(),
+            format!("`{}` needs a `#[storage(hot)]` or `#[storage(cold)]` attribute", field.name))),
+    };
+    let component = match generic_args(&field.ty, "ComponentList")
+    {
+        Some(ref args) if args.len() == 2 => replace_self(&args[1], &input.ty()),
+        _ => return Err(Error::new(field.ty_span(), format!("expected `ComponentList<Self, T>` for `{}`", field.name))),
+    };
+    Ok(List {
+        field,
+        kind,
+        component,
+        replicated: field.attr("replicated").is_some(),
+    })
+}
+
+/// Picks a name for a parameter that goes unused if there's nothing to do with it.
+fn param(used: bool, name: &str) -> String
+{
+    if used { name.to_string() } else { format!("_{}", name) }
+}
+
+/// The `__new`, `__remove_all` and `__move_entity` functions shared by managers and groups.
+fn lifecycle(input: &Input, lists: &[List], groups: &[Group], dynamic: Option<&Field>, manager: &str) -> String
+{
+    let new = match input.fields
+    {
+        Fields::Unit => input.name.to_string(),
+        _ => format!("{} {{ {}{}{} }}", input.name,
+            lists.iter().map(|l| format!("{}: ::ecs::ComponentList::{}(),",
+                l.field.name, l.kind.constructor())).collect::<String>(),
+            groups.iter().map(|g| format!("{}: <{} as ::ecs::ComponentGroup<Self>>::__new(),", g.field.name, g.ty))
+                .collect::<String>(),
+            dynamic.map(|f| format!("{}: ::ecs::DynamicComponents::new(),", f.name)).unwrap_or_default()),
+    };
+    // Dynamic components are cleared by the entity manager, which finds them through these.
+    let accessors = dynamic.map(|f| format!("
+        fn __dynamic(&self) -> Option<&::ecs::DynamicComponents<Self>>
+        {{
+            Some(&self.{field})
+        }}
+
+        fn __dynamic_mut(&mut self) -> Option<&mut ::ecs::DynamicComponents<Self>>
+        {{
+            Some(&mut self.{field})
+        }}", field = f.name)).unwrap_or_default();
+    let entity = param(!lists.is_empty() || !groups.is_empty(), "entity");
+    format!("
+        fn __new() -> Self
+        {{
+            {new}
+        }}
+
+        fn __remove_all(&mut self, {entity}: &::ecs::IndexedEntity<{manager}>)
+        {{
+            {clear}{clear_groups}
+        }}
+
+        fn __move_entity(&mut self, {other}: &mut Self, {from}: &::ecs::IndexedEntity<{manager}>, {to}: &::ecs::IndexedEntity<{manager}>)
+        {{
+            {moves}{group_moves}
+        }}
+        {accessors}",
+        new = new,
+        entity = entity,
+        manager = manager,
+        clear = lists.iter().map(|l| format!("self.{}.__clear({});", l.field.name, entity)).collect::<String>(),
+        clear_groups = groups.iter()
+            .map(|g| format!("::ecs::ComponentGroup::__remove_all(&mut self.{}, {});", g.field.name, entity))
+            .collect::<String>(),
+        other = param(!lists.is_empty() || !groups.is_empty(), "other"),
+        from = param(!lists.is_empty() || !groups.is_empty(), "from"),
+        to = param(!lists.is_empty() || !groups.is_empty(), "to"),
+        moves = lists.iter()
+            .map(|l| format!("self.{0}.__move_entity(&mut other.{0}, from, to);", l.field.name))
+            .collect::<String>(),
+        group_moves = groups.iter()
+            .map(|g| format!("::ecs::ComponentGroup::__move_entity(&mut self.{0}, &mut other.{0}, from, to);", g.field.name))
+            .collect::<String>(),
+        accessors = accessors,
+    )
+}
+
+fn diff(input: &Input, lists: &[List], groups: &[Group], trait_name: &str, bounds: &[String]) -> String
+{
+    // The higher-ranked bounds stop the compiler rejecting this impl outright when a
+    // component type doesn't meet them. Instead, it simply doesn't apply.
+    let mut bounds = bounds.to_vec();
+    bounds.extend(lists.iter().map(|l| format!("for<'__ecs> {}: Clone + PartialEq", l.component)));
+    let used = !lists.is_empty() || !groups.is_empty();
+    format!("
+        impl{impl_params} ::ecs::{trait_name} for {ty} {where_clause}
+        {{
+            type Delta = ({deltas}{group_deltas});
+
+            fn __diff(&self, {old}: &Self, {entities}: &[(::ecs::Entity, Option<usize>, usize)]) -> Self::Delta
+            {{
+                ({diffs}{group_diffs})
+            }}
+
+            fn __apply(&mut self, {delta}: &Self::Delta, {index_of}: &mut dyn FnMut(&::ecs::Entity) -> Option<usize>)
+            {{
+                {applies}{group_applies}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        trait_name = trait_name,
+        ty = input.ty(),
+        where_clause = input.where_clause(&bounds),
+        deltas = lists.iter().map(|l| format!("::ecs::delta::ListDelta<{}>,", l.component)).collect::<String>(),
+        group_deltas = groups.iter().map(|g| format!("<{} as ::ecs::DiffGroup<Self>>::Delta,", g.ty)).collect::<String>(),
+        old = param(used, "old"),
+        entities = param(used, "entities"),
+        diffs = lists.iter().map(|l| format!("self.{0}.__diff(&old.{0}, entities),", l.field.name)).collect::<String>(),
+        group_diffs = groups.iter()
+            .map(|g| format!("::ecs::DiffGroup::__diff(&self.{0}, &old.{0}, entities),", g.field.name))
+            .collect::<String>(),
+        delta = param(used, "delta"),
+        index_of = param(used, "index_of"),
+        applies = lists.iter().enumerate()
+            .map(|(i, l)| format!("self.{}.__apply_delta(&delta.{}, index_of);", l.field.name, i))
+            .collect::<String>(),
+        group_applies = groups.iter().enumerate()
+            .map(|(i, g)| format!("::ecs::DiffGroup::__apply(&mut self.{}, &delta.{}, index_of);", g.field.name, lists.len() + i))
+            .collect::<String>(),
+    )
+}
+
+fn clone(input: &Input, lists: &[List], groups: &[Group], trait_name: &str, manager: &str, bounds: &[String]) -> String
+{
+    let mut bounds = bounds.to_vec();
+    bounds.extend(lists.iter().map(|l| format!("for<'__ecs> {}: Clone", l.component)));
+    let used = !lists.is_empty() || !groups.is_empty();
+    format!("
+        impl{impl_params} ::ecs::{trait_name} for {ty} {where_clause}
+        {{
+            fn __clone_entity(&mut self, {from}: &::ecs::IndexedEntity<{manager}>, {to}: &::ecs::IndexedEntity<{manager}>)
+            {{
+                {clones}{group_clones}
+            }}
+
+            fn __restore(&mut self, {snapshot}: &Self)
+            {{
+                {restores}{group_restores}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        trait_name = trait_name,
+        ty = input.ty(),
+        where_clause = input.where_clause(&bounds),
+        manager = manager,
+        from = param(used, "from"),
+        to = param(used, "to"),
+        snapshot = param(used, "snapshot"),
+        clones = lists.iter().map(|l| format!("self.{}.__clone_entity(from, to);", l.field.name)).collect::<String>(),
+        group_clones = groups.iter()
+            .map(|g| format!("::ecs::CloneGroup::__clone_entity(&mut self.{}, from, to);", g.field.name))
+            .collect::<String>(),
+        restores = lists.iter().map(|l| format!("self.{0}.__restore(&snapshot.{0});", l.field.name)).collect::<String>(),
+        group_restores = groups.iter()
+            .map(|g| format!("::ecs::CloneGroup::__restore(&mut self.{0}, &snapshot.{0});", g.field.name))
+            .collect::<String>(),
+    )
+}
+
+/// Implements `HasComponent` for each component type that only one list could be meant by.
+///
+/// Types that mention a type parameter are left out, since the parameter could turn out to be
+/// the same as another component type.
+fn has_component(input: &Input, lists: &[List]) -> String
+{
+    lists.iter()
+        .filter(|l| lists.iter().filter(|other| other.component == l.component).count() == 1)
+        .filter(|l| !input.generics.mentions_type_param(l.component.parse().unwrap()))
+        .map(|l| format!("
+            impl{impl_params} ::ecs::HasComponent<{component}> for {ty} {where_clause}
+            {{
+                fn list(&self) -> &::ecs::ComponentList<Self, {component}>
+                {{
+                    &self.{field}
+                }}
+
+                fn list_mut(&mut self) -> &mut ::ecs::ComponentList<Self, {component}>
+                {{
+                    &mut self.{field}
+                }}
+            }}",
+            impl_params = input.generics.impl_params(),
+            component = l.component,
+            ty = input.ty(),
+            where_clause = input.where_clause(&[]),
+            field = l.field.name,
+        ))
+        .collect()
+}
+
+/// Implements `HasGroup` for each group type that only one field holds.
+fn has_group(input: &Input, groups: &[Group]) -> String
+{
+    groups.iter()
+        .filter(|g| groups.iter().filter(|other| other.ty == g.ty).count() == 1)
+        .map(|g| format!("
+            impl{impl_params} ::ecs::HasGroup<{group}> for {ty} {where_clause}
+            {{
+                fn group(&self) -> &{group}
+                {{
+                    &self.{field}
+                }}
+
+                fn group_mut(&mut self) -> &mut {group}
+                {{
+                    &mut self.{field}
+                }}
+            }}",
+            impl_params = input.generics.impl_params(),
+            group = g.ty,
+            ty = input.ty(),
+            where_clause = input.where_clause(&[]),
+            field = g.field.name,
+        ))
+        .collect()
+}
+
+/// Declares a struct with one field per component list, along with a `Default` impl.
+fn companion<F>(input: &Input, lists: &[List], name: &str, field_ty: F, default: &str) -> String
+    where F: Fn(&List) -> String
+{
+    let mut fields: String = lists.iter()
+        .map(|l| format!("{} pub {}: {},", l.field.docs(), l.field.name, field_ty(l)))
+        .collect();
+    let mut defaults: String = lists.iter().map(|l| format!("{}: {},", l.field.name, default)).collect();
+    // Generic parameters have to be used by the struct somehow.
+    if !input.generics.is_empty() {
+        fields.push_str(&format!("#[doc(hidden)] pub __marker: ::std::marker::PhantomData<{}>,", input.ty()));
+        defaults.push_str("__marker: ::std::marker::PhantomData,");
+    }
+    format!("
+        {vis} struct {name}{impl_params} {where_clause}
+        {{
+            {fields}
+        }}
+
+        impl{impl_params} Default for {name}{type_params} {where_clause}
+        {{
+            fn default() -> Self
+            {{
+                {name} {{ {defaults} }}
+            }}
+        }}",
+        vis = input.vis,
+        name = name,
+        impl_params = input.generics.impl_params(),
+        type_params = input.generics.type_params(),
+        where_clause = input.where_clause(&[]),
+        fields = fields,
+        defaults = defaults,
+    )
+}
+
+fn builder(input: &Input, lists: &[List], name: &str) -> String
+{
+    let mut code = companion(input, lists, name, |l| format!("Option<{}>", l.component), "None");
+    code.push_str(&format!("
+        impl{impl_params} ::ecs::EntityBuilder<{ty}> for {name}{type_params} {where_clause}
+        {{
+            fn build(self, {e}: ::ecs::BuildData<{ty}>, {c}: &mut {ty})
+            {{
+                {adds}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        name = name,
+        type_params = input.generics.type_params(),
+        where_clause = input.where_clause(&[]),
+        e = param(!lists.is_empty(), "e"),
+        c = param(!lists.is_empty(), "c"),
+        adds = lists.iter()
+            .map(|l| format!("if let Some(component) = self.{0} {{ c.{0}.add(&e, component); }}", l.field.name))
+            .collect::<String>(),
+    ));
+    // Like the deltas, these only apply when every component can be cloned.
+    let bounds: Vec<String> = lists.iter().map(|l| format!("for<'__ecs> {}: Clone", l.component)).collect();
+    let marker = if input.generics.is_empty() { "" } else { "__marker: ::std::marker::PhantomData," };
+    code.push_str(&format!("
+        impl{impl_params} Clone for {name}{type_params} {where_clause}
+        {{
+            fn clone(&self) -> Self
+            {{
+                {name} {{ {clones}{marker} }}
+            }}
+        }}
+
+        impl{impl_params} ::ecs::prefab::Template<{ty}> for {name}{type_params} {where_clause}
+        {{
+            fn overlay(&mut self, {other}: &Self)
+            {{
+                {overlays}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        name = name,
+        type_params = input.generics.type_params(),
+        where_clause = input.where_clause(&bounds),
+        clones = lists.iter().map(|l| format!("{0}: self.{0}.clone(),", l.field.name)).collect::<String>(),
+        marker = marker,
+        other = param(!lists.is_empty(), "other"),
+        overlays = lists.iter()
+            .map(|l| format!("if other.{0}.is_some() {{ self.{0} = other.{0}.clone(); }}", l.field.name))
+            .collect::<String>(),
+    ));
+    code
+}
+
+fn modifier(input: &Input, lists: &[List], name: &str) -> String
+{
+    let mut code = companion(input, lists, name, |l| format!("::ecs::Change<{}>", l.component), "::ecs::Change::Unchanged");
+    code.push_str(&format!("
+        impl{impl_params} ::ecs::EntityModifier<{ty}> for {name}{type_params} {where_clause}
+        {{
+            fn modify(self, {e}: ::ecs::ModifyData<{ty}>, {c}: &mut {ty})
+            {{
+                {applies}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        name = name,
+        type_params = input.generics.type_params(),
+        where_clause = input.where_clause(&[]),
+        e = param(!lists.is_empty(), "e"),
+        c = param(!lists.is_empty(), "c"),
+        applies = lists.iter().map(|l| format!("c.{0}.apply(&e, self.{0});", l.field.name)).collect::<String>(),
+    ));
+    code
+}
+
+fn replicated(input: &Input, lists: &[List]) -> String
+{
+    let replicated: Vec<&List> = lists.iter().filter(|l| l.replicated).collect();
+    format!("
+        impl{impl_params} ::ecs::replication::Replicated for {ty} {where_clause}
+        {{
+            fn __encode(&self, index: usize) -> ::ecs::replication::CerealResult<Vec<Option<Vec<u8>>>>
+            {{
+                Ok(vec![{encodes}])
+            }}
+
+            fn __decode(&mut self, entity: &::ecs::IndexedEntity<{ty}>, changes: &[::ecs::replication::FieldChange]) -> ::ecs::replication::CerealResult<()>
+            {{
+                let mut changes = changes.iter();
+                {decodes}
+                Ok(())
+            }}
+
+            fn __track_changes(&self) -> Vec<u64>
+            {{
+                vec![{tracks}]
+            }}
+
+            fn __changed_since(&self, index: usize, versions: &[u64]) -> bool
+            {{
+                let mut versions = versions.iter();
+                {changed}
+                false
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        where_clause = input.where_clause(&[]),
+        encodes = replicated.iter().map(|l| format!("self.{}.__encode(index)?,", l.field.name)).collect::<String>(),
+        decodes = replicated.iter()
+            .map(|l| format!("if let Some(change) = changes.next() {{ self.{}.__decode(entity, change)?; }}", l.field.name))
+            .collect::<String>(),
+        tracks = replicated.iter().map(|l| format!("self.{}.__track_changes(),", l.field.name)).collect::<String>(),
+        changed = replicated.iter()
+            .map(|l| format!("if versions.next().map_or(true, |&v| self.{}.__changed_since(index, v)) {{ return true; }}", l.field.name))
+            .collect::<String>(),
+    )
+}
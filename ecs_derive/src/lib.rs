@@ -0,0 +1,140 @@
+//! Derive macros for the `ecs` crate.
+//!
+//! These mostly do the same job as the `components!` and `systems!` macros, but work on ordinary
+//! struct declarations, so they support generics, visibility, doc comments on each field and
+//! any other attributes.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate ecs_derive;
+//! extern crate ecs;
+//!
+//! use ecs::ComponentList;
+//!
+//! #[derive(ComponentManager)]
+//! #[builder(EntityInit)]
+//! #[modifier(EntityChange)]
+//! pub struct MyComponents {
+//!     /// Where the entity is.
+//!     #[storage(hot)] pub position: ComponentList<Self, Position>,
+//!     #[storage(cold)] pub team: ComponentList<Self, Team>,
+//! }
+//!
+//! #[derive(ServiceManager, Default)]
+//! pub struct MyServices {
+//!     pub score: u32,
+//! }
+//!
+//! #[derive(SystemManager)]
+//! #[components(MyComponents)]
+//! #[services(MyServices)]
+//! pub struct MySystems {
+//!     #[active(EntitySystem::new(Motion, aspect!(<MyComponents> all: [position])))]
+//!     pub motion: EntitySystem<Motion>,
+//!     #[passive]
+//!     pub tracker: Tracker,
+//! }
+//! ```
+//!
+//! # `ComponentManager`
+//!
+//! Every field must be a `ComponentList` marked `#[storage(hot)]` or `#[storage(cold)]`, which
+//! work like `#[hot]` and `#[cold]` in `components!`, or `#[storage(hot_indexed)]` or
+//! `#[storage(cold_indexed)]` for lists that can be searched by value. Fields marked
+//! `#[replicated]` are sent by the `replication` module (which needs the `serialisation`
+//! feature of `ecs`), in the order they're declared.
+//!
+//! `ecs::HasComponent` is implemented for each component type that only one list holds, as long
+//! as the type doesn't mention one of the struct's type parameters.
+//!
+//! `#[builder(Name)]` generates an `EntityBuilder` with an `Option` for each component, and
+//! `#[modifier(Name)]` generates an `EntityModifier` with an `ecs::Change` for each component.
+//! Both implement `Default`, so only the components of interest need to be given. When every
+//! component is `Clone`, the builder is too, and can be used as an `ecs::prefab::Template`.
+//!
+//! Fields marked `#[plugin]` hold a plugin's component group instead of a component list, and
+//! `ecs::HasGroup` is implemented for them (see the `ecs::plugin` module). A single field of
+//! type `DynamicComponents<Self>` can be marked `#[dynamic]` to hold component lists registered
+//! at runtime (see the `ecs::dynamic` module).
+//!
+//! # `ComponentGroup`
+//!
+//! Declares a plugin's component lists, generic over the component manager they end up in. The
+//! fields work like those of a `ComponentManager`, except they're written `ComponentList<C, T>`
+//! where `C` is one of the struct's type parameters.
+//!
+//! # `SystemManager`
+//!
+//! `#[components(Type)]` names the component manager, and `#[services(Type)]` the service
+//! manager (`()` if left out). Every field must be a system marked `#[active]` or `#[passive]`,
+//! optionally with the expression used to create it, such as `#[active(Motion::new())]`.
+//! Systems without one are created with `Default::default()`. Fields marked `#[plugin]` hold
+//! another `SystemManager` with the same component and service managers, such as a plugin's
+//! systems, which is updated and notified along with the rest.
+//!
+//! # `ServiceManager`
+//!
+//! Implements `ServiceManager`, which has no requirements beyond being `'static`. Fields marked
+//! `#[plugin]` hold a plugin's services, and `ecs::HasService` is implemented for them.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+use parse::{Error, Input, Result};
+
+mod components;
+mod parse;
+mod services;
+mod systems;
+
+#[proc_macro_derive(ComponentManager, attributes(storage, builder, modifier, replicated, plugin, dynamic))]
+pub fn derive_component_manager(input: TokenStream) -> TokenStream
+{
+    expand(input, components::derive, components::stub)
+}
+
+#[proc_macro_derive(ComponentGroup, attributes(storage))]
+pub fn derive_component_group(input: TokenStream) -> TokenStream
+{
+    expand(input, components::derive_group, |_| String::new())
+}
+
+#[proc_macro_derive(SystemManager, attributes(components, services, active, passive, plugin))]
+pub fn derive_system_manager(input: TokenStream) -> TokenStream
+{
+    expand(input, systems::derive, |_| String::new())
+}
+
+#[proc_macro_derive(ServiceManager, attributes(plugin))]
+pub fn derive_service_manager(input: TokenStream) -> TokenStream
+{
+    expand(input, services::derive, |_| String::new())
+}
+
+fn expand<F, S>(input: TokenStream, derive: F, stub: S) -> TokenStream
+    where F: FnOnce(&Input) -> Result<String>, S: FnOnce(&Input) -> String
+{
+    let input = match Input::parse(input)
+    {
+        Ok(input) => input,
+        Err(err) => return err.into_compile_error(),
+    };
+    let expanded = derive(&input).and_then(|code| {
+        // Only a bug in this crate would make the generated code unparseable, but that's better
+        // reported against the struct than as a panic.
+        code.parse().map_err(|_| Error::new(input.name.span(),
+            format!("ecs_derive generated code for `{}` that doesn't parse, which is a bug in ecs_derive", input.name)))
+    });
+    match expanded
+    {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            // The stub stands in for the impl that couldn't be generated, so that the error above
+            // is the only one reported rather than one for every use of the type.
+            let mut tokens = err.into_compile_error();
+            tokens.extend(stub(&input).parse::<TokenStream>().ok());
+            tokens
+        },
+    }
+}
@@ -0,0 +1,536 @@
+
+//! Just enough of a parser for item declarations, working directly on token trees.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::iter::FromIterator;
+
+pub struct Error
+{
+    span: Span,
+    message: String,
+}
+
+impl Error
+{
+    pub fn new<S: Into<String>>(span: Span, message: S) -> Error
+    {
+        Error { span, message: message.into() }
+    }
+
+    /// Expands to `compile_error!("...")`, pointing at the offending tokens.
+    pub fn into_compile_error(self) -> TokenStream
+    {
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let mut args = Group::new(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(message)));
+        args.set_span(self.span);
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.span);
+        let mut semi = Punct::new(';', Spacing::Alone);
+        semi.set_span(self.span);
+        TokenStream::from_iter(vec![
+            TokenTree::Ident(Ident::new("compile_error", self.span)),
+            TokenTree::Punct(bang),
+            TokenTree::Group(args),
+            TokenTree::Punct(semi),
+        ])
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+pub struct Attribute
+{
+    pub name: String,
+    pub span: Span,
+    /// Everything after the name, such as `(Foo)` or `= "docs"`.
+    pub args: Vec<TokenTree>,
+    /// The whole attribute, including the `#`.
+    pub tokens: Vec<TokenTree>,
+}
+
+impl Attribute
+{
+    /// The contents of the parentheses, if the attribute was written as `#[name(...)]`.
+    pub fn list(&self) -> Option<TokenStream>
+    {
+        match self.args.first()
+        {
+            Some(TokenTree::Group(group)) if self.args.len() == 1 && group.delimiter() == Delimiter::Parenthesis =>
+                Some(group.stream()),
+            _ => None,
+        }
+    }
+
+    /// The single identifier given as `#[name(Ident)]`.
+    pub fn ident_arg(&self) -> Result<Ident>
+    {
+        let args: Vec<TokenTree> = self.list().map(|s| s.into_iter().collect()).unwrap_or_default();
+        match args.first()
+        {
+            Some(TokenTree::Ident(ident)) if args.len() == 1 => Ok(ident.clone()),
+            _ => Err(Error::new(self.span, format!("expected `#[{}(Name)]`", self.name))),
+        }
+    }
+}
+
+pub enum Fields
+{
+    Named(Vec<Field>),
+    Tuple(Span),
+    Unit,
+    /// The body of an enum or union.
+    Other(Span),
+}
+
+pub struct Field
+{
+    pub attrs: Vec<Attribute>,
+    pub name: Ident,
+    pub ty: Vec<TokenTree>,
+}
+
+impl Field
+{
+    pub fn attr(&self, name: &str) -> Option<&Attribute>
+    {
+        self.attrs.iter().find(|a| a.name == name)
+    }
+
+    pub fn docs(&self) -> String
+    {
+        self.attrs.iter().filter(|a| a.name == "doc").map(|a| to_string(&a.tokens)).collect()
+    }
+
+    pub fn ty_span(&self) -> Span
+    {
+        self.ty.first().map(|t| t.span()).unwrap_or_else(|| self.name.span())
+    }
+}
+
+struct Param
+{
+    name: String,
+    decl: String,
+    is_type: bool,
+}
+
+/// The generic parameters of an item.
+pub struct Generics
+{
+    params: Vec<Param>,
+}
+
+impl Generics
+{
+    /// The parameters as declared, minus any defaults, for `impl<...>`.
+    pub fn impl_params(&self) -> String
+    {
+        if self.params.is_empty() {
+            return String::new();
+        }
+        let decls: Vec<&str> = self.params.iter().map(|p| &p.decl[..]).collect();
+        format!("<{}>", decls.join(", "))
+    }
+
+    /// Just the names of the parameters, for naming the type.
+    pub fn type_params(&self) -> String
+    {
+        if self.params.is_empty() {
+            return String::new();
+        }
+        let names: Vec<&str> = self.params.iter().map(|p| &p.name[..]).collect();
+        format!("<{}>", names.join(", "))
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.params.is_empty()
+    }
+
+    /// Whether the tokens mention any of the type parameters.
+    pub fn mentions_type_param(&self, tokens: TokenStream) -> bool
+    {
+        tokens.into_iter().any(|token| match token
+        {
+            TokenTree::Ident(ident) => self.params.iter().any(|p| p.is_type && p.name == ident.to_string()),
+            TokenTree::Group(group) => self.mentions_type_param(group.stream()),
+            _ => false,
+        })
+    }
+}
+
+pub struct Input
+{
+    pub attrs: Vec<Attribute>,
+    pub vis: String,
+    pub name: Ident,
+    pub generics: Generics,
+    /// The predicates of the `where` clause, without the `where`.
+    pub predicates: String,
+    pub fields: Fields,
+}
+
+impl Input
+{
+    pub fn parse(input: TokenStream) -> Result<Input>
+    {
+        let mut cursor = Cursor::new(input);
+        let attrs = cursor.attributes()?;
+        let vis = cursor.visibility();
+        let kind = match cursor.next()
+        {
+            Some(TokenTree::Ident(ident)) => ident,
+            other => return Err(Error::new(span_of(&other), "expected an item")),
+        };
+        let is_struct = match &kind.to_string()[..]
+        {
+            "struct" => true,
+            "enum" | "union" => false,
+            _ => return Err(Error::new(kind.span(), "expected a struct, enum or union")),
+        };
+        let name = match cursor.next()
+        {
+            Some(TokenTree::Ident(ident)) => ident,
+            other => return Err(Error::new(span_of(&other), "expected a name")),
+        };
+        let generics = cursor.generics()?;
+
+        let mut predicates = Vec::new();
+        let mut in_where = false;
+        let mut fields = Fields::Unit;
+        while let Some(token) = cursor.next() {
+            match token
+            {
+                TokenTree::Ident(ref ident) if !in_where && ident.to_string() == "where" => in_where = true,
+                TokenTree::Group(ref group) if group.delimiter() == Delimiter::Brace => {
+                    fields = if is_struct {
+                        Fields::Named(parse_fields(group.stream())?)
+                    } else {
+                        Fields::Other(group.span())
+                    };
+                    break;
+                },
+                TokenTree::Group(ref group) if !in_where && group.delimiter() == Delimiter::Parenthesis =>
+                    fields = Fields::Tuple(group.span()),
+                TokenTree::Punct(ref punct) if punct.as_char() == ';' => break,
+                token => predicates.push(token),
+            }
+        }
+
+        Ok(Input {
+            attrs,
+            vis,
+            name,
+            generics,
+            predicates: to_string(&predicates),
+            fields,
+        })
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attribute>
+    {
+        self.attrs.iter().find(|a| a.name == name)
+    }
+
+    /// The type being derived for, as it would be named inside its own impls.
+    pub fn ty(&self) -> String
+    {
+        format!("{}{}", self.name, self.generics.type_params())
+    }
+
+    /// A `where` clause with the item's own predicates and any extra ones.
+    pub fn where_clause(&self, extra: &[String]) -> String
+    {
+        let mut predicates = self.predicates.trim().trim_end_matches(',').to_string();
+        for predicate in extra {
+            if !predicates.is_empty() {
+                predicates.push_str(", ");
+            }
+            predicates.push_str(predicate);
+        }
+        if predicates.is_empty() {
+            predicates
+        } else {
+            format!("where {}", predicates)
+        }
+    }
+}
+
+fn parse_fields(body: TokenStream) -> Result<Vec<Field>>
+{
+    let mut fields = Vec::new();
+    for tokens in split_top_level(body.into_iter().collect(), ',') {
+        let mut cursor = Cursor::from_vec(tokens);
+        let attrs = cursor.attributes()?;
+        cursor.visibility();
+        let name = match cursor.next()
+        {
+            Some(TokenTree::Ident(ident)) => ident,
+            other => return Err(Error::new(span_of(&other), "expected a field name")),
+        };
+        match cursor.next()
+        {
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == ':' => (),
+            other => return Err(Error::new(span_of(&other), "expected `:`")),
+        }
+        let ty = cursor.rest();
+        if ty.is_empty() {
+            return Err(Error::new(name.span(), format!("expected a type for `{}`", name)));
+        }
+        fields.push(Field { attrs, name, ty });
+    }
+    Ok(fields)
+}
+
+/// Splits tokens on a separator that isn't nested within angle brackets.
+pub fn split_top_level(tokens: Vec<TokenTree>, separator: char) -> Vec<Vec<TokenTree>>
+{
+    let mut parts = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0usize;
+    let mut after_dash = false;
+    for token in tokens {
+        let mut is_dash = false;
+        if let TokenTree::Punct(ref punct) = token {
+            match punct.as_char()
+            {
+                '<' => depth += 1,
+                // The `>` of `->` doesn't close anything.
+                '>' if !after_dash => depth = depth.saturating_sub(1),
+                '-' => is_dash = punct.spacing() == Spacing::Joint,
+                c if c == separator && depth == 0 => {
+                    parts.push(::std::mem::take(&mut current));
+                    after_dash = false;
+                    continue;
+                },
+                _ => (),
+            }
+        }
+        after_dash = is_dash;
+        current.push(token);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Finds the generic arguments of the last `Name<...>` in a type.
+pub fn generic_args(ty: &[TokenTree], name: &str) -> Option<Vec<Vec<TokenTree>>>
+{
+    let start = ty.windows(2).rposition(|pair| match (&pair[0], &pair[1])
+    {
+        (TokenTree::Ident(ident), TokenTree::Punct(punct)) =>
+            ident.to_string() == name && punct.as_char() == '<',
+        _ => false,
+    })? + 2;
+    let mut depth = 1usize;
+    let mut after_dash = false;
+    for (i, token) in ty[start..].iter().enumerate() {
+        let mut is_dash = false;
+        if let TokenTree::Punct(ref punct) = *token {
+            match punct.as_char()
+            {
+                '<' => depth += 1,
+                '>' if !after_dash => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(split_top_level(ty[start..start + i].to_vec(), ','));
+                    }
+                },
+                '-' => is_dash = punct.spacing() == Spacing::Joint,
+                _ => (),
+            }
+        }
+        after_dash = is_dash;
+    }
+    None
+}
+
+/// Replaces every `Self` with the given type.
+pub fn replace_self(tokens: &[TokenTree], ty: &str) -> String
+{
+    let replacement: TokenStream = ty.parse().unwrap();
+    to_string(&replace_self_tokens(tokens.iter().cloned(), &replacement))
+}
+
+fn replace_self_tokens<I: Iterator<Item=TokenTree>>(tokens: I, replacement: &TokenStream) -> Vec<TokenTree>
+{
+    let mut result = Vec::new();
+    for token in tokens {
+        match token
+        {
+            TokenTree::Ident(ref ident) if ident.to_string() == "Self" => result.extend(replacement.clone()),
+            TokenTree::Group(ref group) => {
+                let stream = TokenStream::from_iter(replace_self_tokens(group.stream().into_iter(), replacement));
+                let mut new = Group::new(group.delimiter(), stream);
+                new.set_span(group.span());
+                result.push(TokenTree::Group(new));
+            },
+            token => result.push(token),
+        }
+    }
+    result
+}
+
+pub fn to_string(tokens: &[TokenTree]) -> String
+{
+    TokenStream::from_iter(tokens.iter().cloned()).to_string()
+}
+
+fn span_of(token: &Option<TokenTree>) -> Span
+{
+    token.as_ref().map(|t| t.span()).unwrap_or_else(Span::call_site)
+}
+
+struct Cursor
+{
+    tokens: Vec<TokenTree>,
+    pos: usize,
+}
+
+impl Cursor
+{
+    fn new(stream: TokenStream) -> Cursor
+    {
+        Cursor::from_vec(stream.into_iter().collect())
+    }
+
+    fn from_vec(tokens: Vec<TokenTree>) -> Cursor
+    {
+        Cursor { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&TokenTree>
+    {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<TokenTree>
+    {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn rest(&mut self) -> Vec<TokenTree>
+    {
+        let rest = self.tokens.get(self.pos..).map(|r| r.to_vec()).unwrap_or_default();
+        self.pos = self.tokens.len();
+        rest
+    }
+
+    fn peek_punct(&self, c: char) -> bool
+    {
+        match self.peek()
+        {
+            Some(TokenTree::Punct(punct)) => punct.as_char() == c,
+            _ => false,
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Attribute>>
+    {
+        let mut attrs = Vec::new();
+        while self.peek_punct('#') {
+            let hash = self.next().unwrap();
+            let group = match self.next()
+            {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
+                other => return Err(Error::new(span_of(&other), "expected an attribute")),
+            };
+            let mut inner: Vec<TokenTree> = group.stream().into_iter().collect();
+            let split = inner.iter().position(|t| match *t
+            {
+                TokenTree::Group(_) => true,
+                TokenTree::Punct(ref punct) => punct.as_char() == '=',
+                _ => false,
+            }).unwrap_or(inner.len());
+            let args = inner.split_off(split);
+            attrs.push(Attribute {
+                name: to_string(&inner).replace(' ', ""),
+                span: group.span(),
+                args,
+                tokens: vec![hash, TokenTree::Group(group)],
+            });
+        }
+        Ok(attrs)
+    }
+
+    fn visibility(&mut self) -> String
+    {
+        let is_pub = match self.peek()
+        {
+            Some(TokenTree::Ident(ident)) => ident.to_string() == "pub",
+            _ => false,
+        };
+        if !is_pub {
+            return String::new();
+        }
+        let mut vis = vec![self.next().unwrap()];
+        if let Some(TokenTree::Group(group)) = self.peek() {
+            if group.delimiter() == Delimiter::Parenthesis {
+                vis.push(TokenTree::Group(group.clone()));
+            }
+        }
+        if vis.len() == 2 {
+            self.pos += 1;
+        }
+        to_string(&vis)
+    }
+
+    fn generics(&mut self) -> Result<Generics>
+    {
+        if !self.peek_punct('<') {
+            return Ok(Generics { params: Vec::new() });
+        }
+        let open = self.next().unwrap();
+        let mut tokens = Vec::new();
+        let mut depth = 1usize;
+        let mut after_dash = false;
+        loop {
+            let token = match self.next()
+            {
+                Some(token) => token,
+                None => return Err(Error::new(open.span(), "unclosed generic parameters")),
+            };
+            let mut is_dash = false;
+            if let TokenTree::Punct(ref punct) = token {
+                match punct.as_char()
+                {
+                    '<' => depth += 1,
+                    '>' if !after_dash => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    },
+                    '-' => is_dash = punct.spacing() == Spacing::Joint,
+                    _ => (),
+                }
+            }
+            after_dash = is_dash;
+            tokens.push(token);
+        }
+
+        let mut params = Vec::new();
+        for mut param in split_top_level(tokens, ',') {
+            let (name, is_type) = match (param.first(), param.get(1))
+            {
+                (Some(TokenTree::Punct(tick)), Some(TokenTree::Ident(ident))) if tick.as_char() == '\'' =>
+                    (format!("'{}", ident), false),
+                (Some(TokenTree::Ident(konst)), Some(TokenTree::Ident(ident))) if konst.to_string() == "const" =>
+                    (ident.to_string(), false),
+                (Some(TokenTree::Ident(ident)), _) => (ident.to_string(), true),
+                (first, _) => return Err(Error::new(span_of(&first.cloned()), "expected a generic parameter")),
+            };
+            // Defaults are only allowed where the type is declared.
+            if let Some(default) = split_top_level(param.clone(), '=').first() {
+                param = default.clone();
+            }
+            params.push(Param { name, decl: to_string(&param), is_type });
+        }
+        Ok(Generics { params })
+    }
+}
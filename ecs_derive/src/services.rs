@@ -0,0 +1,43 @@
+
+use parse::{replace_self, Fields, Input, Result};
+
+pub fn derive(input: &Input) -> Result<String>
+{
+    let mut code = format!("impl{} ::ecs::ServiceManager for {} {} {{}}",
+        input.generics.impl_params(), input.ty(), input.where_clause(&[]));
+
+    // Each `#[plugin]` field is a plugin's services.
+    let plugins: Vec<_> = match input.fields
+    {
+        Fields::Named(ref fields) => fields.iter()
+            .filter(|f| f.attr("plugin").is_some())
+            .map(|f| (f, replace_self(&f.ty, &input.ty())))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for &(field, ref ty) in &plugins {
+        if plugins.iter().filter(|&(_, other)| other == ty).count() > 1 {
+            continue;
+        }
+        code.push_str(&format!("
+            impl{impl_params} ::ecs::HasService<{service}> for {ty} {where_clause}
+            {{
+                fn service(&self) -> &{service}
+                {{
+                    &self.{field}
+                }}
+
+                fn service_mut(&mut self) -> &mut {service}
+                {{
+                    &mut self.{field}
+                }}
+            }}",
+            impl_params = input.generics.impl_params(),
+            service = ty,
+            ty = input.ty(),
+            where_clause = input.where_clause(&[]),
+            field = field.name,
+        ));
+    }
+    Ok(code)
+}
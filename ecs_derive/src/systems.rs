@@ -0,0 +1,135 @@
+
+use parse::{Error, Field, Fields, Input, Result};
+
+enum Role
+{
+    Active,
+    Passive,
+    /// Another `SystemManager`, such as a plugin's systems.
+    Plugin,
+}
+
+struct System<'a>
+{
+    field: &'a Field,
+    role: Role,
+    init: String,
+}
+
+pub fn derive(input: &Input) -> Result<String>
+{
+    let components = match input.attr("components")
+    {
+        Some(attr) => match attr.list()
+        {
+            Some(ref ty) if !ty.is_empty() => ty.to_string(),
+            _ => return Err(Error::new(attr.span, "expected `#[components(Type)]`")),
+        },
+        None => return Err(Error::new(input.name.span(), "missing a `#[components(Type)]` attribute")),
+    };
+    let services = match input.attr("services")
+    {
+        Some(attr) => match attr.list()
+        {
+            Some(ref ty) if !ty.is_empty() => ty.to_string(),
+            _ => return Err(Error::new(attr.span, "expected `#[services(Type)]`")),
+        },
+        None => "()".to_string(),
+    };
+    let systems = match input.fields
+    {
+        Fields::Named(ref fields) => fields.iter().map(system).collect::<Result<Vec<_>>>()?,
+        Fields::Unit => Vec::new(),
+        Fields::Tuple(span) | Fields::Other(span) =>
+            return Err(Error::new(span, "`SystemManager` can only be derived for structs with named fields")),
+    };
+
+    let new = match input.fields
+    {
+        Fields::Unit => input.name.to_string(),
+        _ => format!("{} {{ {} }}", input.name,
+            systems.iter().map(|s| format!("{}: {},", s.field.name, s.init)).collect::<String>()),
+    };
+    let notify = |function: &str| -> String {
+        systems.iter()
+            .map(|s| match s.role
+            {
+                Role::Plugin => format!("::ecs::SystemManager::__{}(&mut self.{}, _en, _co, _se);", function, s.field.name),
+                _ => format!("::ecs::System::{}(&mut self.{}, &_en, _co, _se);", function, s.field.name),
+            })
+            .collect()
+    };
+    Ok(format!("
+        impl{impl_params} ::ecs::SystemManager for {ty} {where_clause}
+        {{
+            type Components = {components};
+            type Services = {services};
+
+            fn __new() -> Self
+            {{
+                {new}
+            }}
+
+            fn __activated(&mut self, _en: ::ecs::EntityData<{components}>, _co: &{components}, _se: &mut {services})
+            {{
+                {activated}
+            }}
+
+            fn __reactivated(&mut self, _en: ::ecs::EntityData<{components}>, _co: &{components}, _se: &mut {services})
+            {{
+                {reactivated}
+            }}
+
+            fn __deactivated(&mut self, _en: ::ecs::EntityData<{components}>, _co: &{components}, _se: &mut {services})
+            {{
+                {deactivated}
+            }}
+
+            fn __update(&mut self, _co: &mut ::ecs::DataHelper<{components}, {services}>)
+            {{
+                {update}
+            }}
+        }}",
+        impl_params = input.generics.impl_params(),
+        ty = input.ty(),
+        where_clause = input.where_clause(&[]),
+        components = components,
+        services = services,
+        new = new,
+        activated = notify("activated"),
+        reactivated = notify("reactivated"),
+        deactivated = notify("deactivated"),
+        update = systems.iter()
+            .map(|s| match s.role
+            {
+                Role::Active => format!("::ecs::Process::process(&mut self.{}, _co);", s.field.name),
+                Role::Passive => String::new(),
+                Role::Plugin => format!("::ecs::SystemManager::__update(&mut self.{}, _co);", s.field.name),
+            })
+            .collect::<String>(),
+    ))
+}
+
+fn system(field: &Field) -> Result<System<'_>>
+{
+    let mut roles = vec![("active", Role::Active), ("passive", Role::Passive), ("plugin", Role::Plugin)].into_iter()
+        .filter_map(|(name, role)| field.attr(name).map(|attr| (attr, role)));
+    let (attr, role) = match (roles.next(), roles.next())
+    {
+        (Some(found), None) => found,
+        (Some((attr, _)), Some(_)) =>
+            return Err(Error::new(attr.span, format!("`{}` can only be one of `#[active]`, `#[passive]` or `#[plugin]`", field.name))),
+        (None, _) =>
+            return Err(Error::new(field.name.span(), format!("`{}` needs an `#[active]`, `#[passive]` or `#[plugin]` attribute", field.name))),
+    };
+    let init = match (attr.list(), &role)
+    {
+        (None, &Role::Plugin) if attr.args.is_empty() => "::ecs::SystemManager::__new()".to_string(),
+        (_, &Role::Plugin) => return Err(Error::new(attr.span, "expected `#[plugin]`")),
+        (Some(ref init), _) if !init.is_empty() => init.to_string(),
+        (Some(_), _) => return Err(Error::new(attr.span, "expected an expression to create the system")),
+        (None, _) if attr.args.is_empty() => "::std::default::Default::default()".to_string(),
+        (None, _) => return Err(Error::new(attr.span, format!("expected `#[{0}]` or `#[{0}(expression)]`", attr.name))),
+    };
+    Ok(System { field, role, init })
+}
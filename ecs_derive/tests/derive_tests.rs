@@ -0,0 +1,178 @@
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::{Change, ComponentList, DataHelper, DynamicComponents, EntityData, EntityIter, World};
+use ecs::{Process, System};
+use ecs::prefab::{Prefab, PrefabRegistry};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Team(pub u8);
+
+#[derive(ComponentManager, Clone)]
+#[builder(EntityInit)]
+#[modifier(EntityChange)]
+pub struct TestComponents
+{
+    /// Where the entity is.
+    #[storage(hot)] pub position: ComponentList<Self, Position>,
+    #[storage(cold_indexed)] pub(crate) team: ComponentList<TestComponents, Team>,
+}
+
+#[derive(ServiceManager, Clone, Default)]
+pub struct TestServices
+{
+    pub steps: u32,
+}
+
+#[derive(SystemManager)]
+#[components(TestComponents)]
+#[services(TestServices)]
+pub struct TestSystems
+{
+    #[active(EntitySystem::new(Motion, aspect!(<TestComponents> all: [position])))]
+    pub motion: EntitySystem<Motion>,
+    #[active]
+    pub counter: Counter,
+    #[passive]
+    pub tracker: Tracker,
+}
+
+pub struct Motion;
+impl System for Motion { type Components = TestComponents; type Services = TestServices; }
+impl EntityProcess for Motion
+{
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, TestServices>)
+    {
+        for e in entities {
+            data.position[e].0 += 1;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Counter;
+impl System for Counter { type Components = TestComponents; type Services = TestServices; }
+impl Process for Counter
+{
+    fn process(&mut self, data: &mut DataHelper<TestComponents, TestServices>)
+    {
+        data.services.steps += 1;
+    }
+}
+
+/// Counts the active entities.
+#[derive(Default)]
+pub struct Tracker(pub usize);
+impl System for Tracker
+{
+    type Components = TestComponents;
+    type Services = TestServices;
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut TestServices) { self.0 += 1; }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut TestServices) { self.0 -= 1; }
+}
+
+#[test]
+fn test_derived_managers()
+{
+    let mut world = World::<TestSystems>::new();
+    let entity = world.create_entity(EntityInit {
+        position: Some(Position(1)),
+        team: Some(Team(2)),
+    });
+    // Builders can be used as prefab templates.
+    let mut prefabs = PrefabRegistry::new();
+    prefabs.insert("unit", Prefab::new(EntityInit { position: None, team: Some(Team(1)) }));
+    let other = world.create_entity(prefabs.instance("unit", EntityInit {
+        team: Some(Team(3)),
+        ..Default::default()
+    }).unwrap());
+    world.update();
+    world.update();
+
+    assert_eq!(world.services.steps, 2);
+    assert_eq!(world.systems.tracker.0, 2);
+    assert_eq!(world.with_entity_data(&entity, |e, c| (c.position.get(&e), c.team.get(&e))),
+        Some((Some(Position(3)), Some(Team(2)))));
+
+    let before = world.snapshot();
+    world.modify_entity(entity, EntityChange {
+        team: Change::Remove,
+        ..Default::default()
+    });
+    world.modify_entity(other, EntityChange {
+        position: Change::Set(Position(10)),
+        team: Change::Set(Team(4)),
+    });
+    assert_eq!(world.with_entity_data(&entity, |e, c| (c.position.get(&e), c.team.get(&e))),
+        Some((Some(Position(3)), None)));
+    assert_eq!(world.with_entity_data(&other, |e, c| (c.position.get(&e), c.team.get(&e))),
+        Some((Some(Position(10)), Some(Team(4)))));
+    assert_eq!(world.team.search().unwrap().find(&Team(4)), vec![other]);
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![]);
+
+    let delta = world.changes_since(&before);
+    assert_eq!(delta.components.0.added, vec![(other, Position(10))]);
+    assert_eq!(delta.components.1.changed, vec![(other, Team(4))]);
+    assert_eq!(delta.components.1.removed, vec![entity]);
+
+    world.remove_entity(other);
+    world.flush_queue();
+    assert_eq!(world.systems.tracker.0, 1);
+}
+
+#[derive(ComponentManager)]
+#[builder(GenericInit)]
+pub struct GenericComponents<T> where T: Clone + 'static
+{
+    #[storage(hot)] pub value: ComponentList<Self, T>,
+    #[storage(cold)] pub history: ComponentList<Self, Vec<T>>,
+    #[dynamic] pub extra: DynamicComponents<Self>,
+}
+
+#[derive(SystemManager)]
+#[components(GenericComponents<T>)]
+pub struct GenericSystems<T: Clone + 'static>
+{
+    #[active(EntitySystem::new(Record(::std::marker::PhantomData), aspect!(<GenericComponents<T>> all: [value, history])))]
+    pub record: EntitySystem<Record<T>>,
+}
+
+pub struct Record<T>(::std::marker::PhantomData<T>);
+impl<T: Clone + 'static> System for Record<T> { type Components = GenericComponents<T>; type Services = (); }
+impl<T: Clone + 'static> EntityProcess for Record<T>
+{
+    fn process(&mut self, entities: EntityIter<GenericComponents<T>>, data: &mut DataHelper<GenericComponents<T>, ()>)
+    {
+        for e in entities {
+            let value = data.value[e].clone();
+            data.history[e].push(value);
+        }
+    }
+}
+
+#[test]
+fn test_generic_managers()
+{
+    let mut world = World::<GenericSystems<&'static str>>::new();
+    world.extra.register("weight", ComponentList::<_, u32>::cold());
+    let entity = world.create_entity(GenericInit {
+        value: Some("a"),
+        history: Some(Vec::new()),
+        ..Default::default()
+    });
+    world.update();
+    world.modify_entity(entity, |e: ecs::ModifyData<GenericComponents<&'static str>>, c: &mut GenericComponents<&'static str>| {
+        c.value[e] = "b";
+        c.extra.list_mut::<u32>().unwrap().insert(&e, 3);
+    });
+    world.update();
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.history.get(&e)), Some(Some(vec!["a", "b"])));
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.extra.has_named("weight", &e)), Some(true));
+}
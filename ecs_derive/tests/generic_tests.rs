@@ -0,0 +1,86 @@
+
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::{BuildData, ComponentList, World};
+
+/// What a library of reusable systems might look like.
+mod physics
+{
+    use ecs::{Aspect, DataHelper, EntityIter, HasComponent, System};
+    use ecs::system::{EntityProcess, EntitySystem};
+    use std::marker::PhantomData;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Position(pub i32);
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Velocity(pub i32);
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Frozen;
+
+    pub struct Motion<C>(PhantomData<C>);
+
+    impl<C> System for Motion<C> where C: HasComponent<Position> + HasComponent<Velocity>
+    {
+        type Components = C;
+        type Services = ();
+    }
+
+    impl<C> EntityProcess for Motion<C> where C: HasComponent<Position> + HasComponent<Velocity>
+    {
+        fn process(&mut self, entities: EntityIter<C>, data: &mut DataHelper<C, ()>)
+        {
+            for e in entities {
+                let velocity = data.list::<Velocity>()[e].0;
+                data.list_mut::<Position>()[e].0 += velocity;
+            }
+        }
+    }
+
+    #[derive(SystemManager)]
+    #[components(C)]
+    pub struct PhysicsSystems<C>
+        where C: HasComponent<Position> + HasComponent<Velocity> + HasComponent<Frozen>
+    {
+        #[active(EntitySystem::new(Motion(PhantomData),
+            Aspect::all().with::<Position>().with::<Velocity>().without::<Frozen>()))]
+        pub motion: EntitySystem<Motion<C>>,
+    }
+}
+
+use physics::{Frozen, PhysicsSystems, Position, Velocity};
+
+#[derive(ComponentManager)]
+pub struct GameComponents
+{
+    #[storage(hot)] pub position: ComponentList<Self, Position>,
+    #[storage(hot)] pub velocity: ComponentList<Self, Velocity>,
+    #[storage(cold)] pub frozen: ComponentList<Self, Frozen>,
+    // Two lists of the same type don't get a `HasComponent` impl, but don't stop the others.
+    #[storage(cold)] pub name: ComponentList<Self, String>,
+    #[storage(cold)] pub title: ComponentList<Self, String>,
+}
+
+#[test]
+fn test_generic_systems()
+{
+    let mut world = World::<PhysicsSystems<GameComponents>>::new();
+    let moving = world.create_entity(|e: BuildData<GameComponents>, c: &mut GameComponents| {
+        c.position.add(&e, Position(0));
+        c.velocity.add(&e, Velocity(2));
+        c.name.add(&e, "moving".to_string());
+    });
+    let frozen = world.create_entity(|e: BuildData<GameComponents>, c: &mut GameComponents| {
+        c.position.add(&e, Position(0));
+        c.velocity.add(&e, Velocity(2));
+        c.frozen.add(&e, Frozen);
+    });
+    world.update();
+    world.update();
+
+    assert_eq!(world.with_entity_data(&moving, |e, c| c.position[e]), Some(Position(4)));
+    assert_eq!(world.with_entity_data(&frozen, |e, c| c.position[e]), Some(Position(0)));
+}
@@ -0,0 +1,193 @@
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::{BuildData, ComponentList, World};
+
+mod physics
+{
+    use ecs::{Aspect, ComponentList, ComponentManager, DataHelper, EntityIter};
+    use ecs::{HasGroup, HasService, ServiceManager, System};
+    use ecs::system::{EntityProcess, EntitySystem};
+    use std::marker::PhantomData;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Position(pub i32);
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Velocity(pub i32);
+
+    #[derive(ComponentGroup, Clone)]
+    pub struct PhysicsComponents<C: ComponentManager>
+    {
+        #[storage(hot)] pub position: ComponentList<C, Position>,
+        #[storage(hot)] pub velocity: ComponentList<C, Velocity>,
+    }
+
+    #[derive(Clone, Default)]
+    pub struct PhysicsServices
+    {
+        pub gravity: i32,
+    }
+
+    pub struct Motion<C, M>(PhantomData<(C, M)>);
+
+    impl<C, M> System for Motion<C, M>
+        where C: HasGroup<PhysicsComponents<C>>, M: ServiceManager + HasService<PhysicsServices>
+    {
+        type Components = C;
+        type Services = M;
+    }
+
+    impl<C, M> EntityProcess for Motion<C, M>
+        where C: HasGroup<PhysicsComponents<C>>, M: ServiceManager + HasService<PhysicsServices>
+    {
+        fn process(&mut self, entities: EntityIter<C>, data: &mut DataHelper<C, M>)
+        {
+            let gravity = data.service::<PhysicsServices>().gravity;
+            for e in entities {
+                let physics = data.group_mut::<PhysicsComponents<C>>();
+                physics.velocity[e].0 -= gravity;
+                let velocity = physics.velocity[e].0;
+                physics.position[e].0 += velocity;
+            }
+        }
+    }
+
+    #[derive(SystemManager)]
+    #[components(C)]
+    #[services(M)]
+    pub struct PhysicsSystems<C, M>
+        where C: HasGroup<PhysicsComponents<C>>, M: ServiceManager + HasService<PhysicsServices>
+    {
+        #[active(EntitySystem::new(Motion(PhantomData),
+            Aspect::all().in_group(|g: &PhysicsComponents<C>, e| g.position.has(e) && g.velocity.has(e))))]
+        pub motion: EntitySystem<Motion<C, M>>,
+    }
+}
+
+mod audio
+{
+    use ecs::{DataHelper, EntityData, Process, System};
+
+    #[derive(Clone, Default)]
+    pub struct AudioServices
+    {
+        pub played: Vec<&'static str>,
+    }
+
+    /// Plays a sound whenever an entity appears.
+    #[derive(Default)]
+    pub struct Spawned(pub usize);
+
+    impl System for Spawned
+    {
+        type Components = ::GameComponents;
+        type Services = ::GameServices;
+        fn activated(&mut self, _: &EntityData<::GameComponents>, _: &::GameComponents, services: &mut ::GameServices)
+        {
+            self.0 += 1;
+            services.audio.played.push("spawn");
+        }
+    }
+
+    pub struct Mixer;
+
+    impl System for Mixer { type Components = ::GameComponents; type Services = ::GameServices; }
+
+    impl Process for Mixer
+    {
+        fn process(&mut self, data: &mut DataHelper<::GameComponents, ::GameServices>)
+        {
+            data.service_mut::<AudioServices>().played.push("mix");
+        }
+    }
+
+    #[derive(SystemManager)]
+    #[components(::GameComponents)]
+    #[services(::GameServices)]
+    pub struct AudioSystems
+    {
+        #[passive] pub spawned: Spawned,
+        #[active(Mixer)] pub mixer: Mixer,
+    }
+}
+
+use audio::{AudioServices, AudioSystems};
+use physics::{PhysicsComponents, PhysicsServices, PhysicsSystems, Position, Velocity};
+
+#[derive(ComponentManager, Clone)]
+pub struct GameComponents
+{
+    #[plugin] pub physics: PhysicsComponents<Self>,
+    #[storage(cold)] pub name: ComponentList<Self, &'static str>,
+}
+
+#[derive(ServiceManager, Clone, Default)]
+pub struct GameServices
+{
+    #[plugin] pub physics: PhysicsServices,
+    #[plugin] pub audio: AudioServices,
+}
+
+systems! {
+    struct GameSystems<GameComponents, GameServices> {
+        active: {
+        },
+        passive: {
+        },
+        plugins: {
+            physics: PhysicsSystems<GameComponents, GameServices>,
+            audio: AudioSystems,
+        }
+    }
+}
+
+#[test]
+fn test_plugins()
+{
+    let mut world = World::<GameSystems>::new();
+    world.services.physics.gravity = 1;
+    let ball = world.create_entity(|e: BuildData<GameComponents>, c: &mut GameComponents| {
+        c.physics.position.add(&e, Position(10));
+        c.physics.velocity.add(&e, Velocity(0));
+        c.name.add(&e, "ball");
+    });
+    world.update();
+    world.update();
+
+    assert_eq!(world.with_entity_data(&ball, |e, c| c.physics.position[e]), Some(Position(7)));
+    assert_eq!(world.systems.audio.spawned.0, 1);
+    assert_eq!(world.services.audio.played, vec!["spawn", "mix", "mix"]);
+
+    // Plugin systems can be run on their own by name.
+    process!(world, physics.motion);
+    process!(world, audio.mixer);
+    assert_eq!(world.with_entity_data(&ball, |e, c| c.physics.position[e]), Some(Position(4)));
+    assert_eq!(world.services.audio.played.len(), 4);
+
+    // So does cloning.
+    let copy = world.clone_entity(&ball);
+    assert_eq!(world.with_entity_data(&copy, |e, c| (c.physics.position[e], c.name[e])), Some((Position(4), "ball")));
+    world.remove_entity(copy);
+    world.flush_queue();
+
+    // And merging worlds.
+    let mut other = World::<GameSystems>::new();
+    let rock = other.create_entity(|e: BuildData<GameComponents>, c: &mut GameComponents| {
+        c.physics.position.add(&e, Position(1));
+        c.name.add(&e, "rock");
+    });
+    let rock = world.merge(other).map(rock);
+    assert_eq!(world.with_entity_data(&rock, |e, c| (c.physics.position[e], c.name[e])), Some((Position(1), "rock")));
+    world.remove_entity(rock);
+    world.flush_queue();
+
+    // Deltas cover the lists in component groups too.
+    let before = world.snapshot();
+    world.update();
+    let delta = world.changes_since(&before);
+    assert_eq!((delta.components.1).0.changed, vec![(ball, Position(0))]);
+}
@@ -0,0 +1,13 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::{ComponentList, ComponentManager};
+
+#[derive(ComponentGroup)]
+pub struct Physics<C: ComponentManager, D: ComponentManager> {
+    #[storage(hot)] pub position: ComponentList<C, i32>,
+    #[storage(hot)] pub velocity: ComponentList<D, i32>,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::ComponentList;
+
+#[derive(ComponentManager)]
+pub struct Components {
+    #[storage(hot)] pub health: ComponentList<Self, u32>,
+}
+
+#[derive(ComponentGroup)]
+pub struct Physics {
+    #[storage(hot)] pub position: ComponentList<Components, i32>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+#[derive(SystemManager)]
+pub struct Systems {
+}
+
+fn main() {}
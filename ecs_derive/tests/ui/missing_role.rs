@@ -0,0 +1,18 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+#[derive(ComponentManager)]
+pub struct Components {
+}
+
+#[derive(Default)]
+pub struct Motion;
+
+#[derive(SystemManager)]
+#[components(Components)]
+pub struct Systems {
+    pub motion: Motion,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::ComponentList;
+
+#[derive(ComponentManager)]
+pub struct Components {
+    pub position: ComponentList<Self, i32>,
+}
+
+fn main() {}
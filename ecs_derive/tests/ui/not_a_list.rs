@@ -0,0 +1,10 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+#[derive(ComponentManager)]
+pub struct Components {
+    #[storage(hot)] pub position: Vec<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::ComponentList;
+
+#[derive(ComponentManager)]
+pub struct Components(ComponentList<Components, i32>);
+
+fn main() {}
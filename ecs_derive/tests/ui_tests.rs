@@ -0,0 +1,10 @@
+extern crate trybuild;
+
+/// Checks the errors given for bad declarations, which are compared against the `.stderr` file
+/// next to each case. Run with `TRYBUILD=overwrite` to update them.
+#[test]
+fn test_compile_errors()
+{
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
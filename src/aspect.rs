@@ -1,7 +1,13 @@
 
 use {ComponentManager, EntityData};
+use component::{Component, HasComponent};
+use plugin::HasGroup;
 
-pub struct Aspect<T: ComponentManager>(Box<Fn(&EntityData<T>, &T) -> bool + 'static>);
+/// Checks an entity's components, to decide whether a system or group should handle it.
+///
+/// The check has to be `Send` and `Sync`, since entity groups keep their aspects in the
+/// `DataHelper`, and systems and observers keep theirs in the `World`.
+pub struct Aspect<T: ComponentManager>(Box<Fn(&EntityData<T>, &T) -> bool + Send + Sync + 'static>);
 
 impl<T: ComponentManager> Aspect<T>
 {
@@ -16,11 +22,58 @@ impl<T: ComponentManager> Aspect<T>
     }
 
     #[doc(hidden)]
-    pub fn __new(inner: Box<Fn(&EntityData<T>, &T) -> bool + 'static>) -> Aspect<T>
+    pub fn __new(inner: Box<Fn(&EntityData<T>, &T) -> bool + Send + Sync + 'static>) -> Aspect<T>
     {
         Aspect(inner)
     }
 
+    /// Also requires a `U` component, for code that's generic over the component manager.
+    pub fn with<U: Component>(self) -> Aspect<T> where T: HasComponent<U>
+    {
+        Aspect(Box::new(move |en, co| (self.0)(en, co) && co.list().has(en)))
+    }
+
+    /// Also requires there to be no `U` component.
+    pub fn without<U: Component>(self) -> Aspect<T> where T: HasComponent<U>
+    {
+        Aspect(Box::new(move |en, co| (self.0)(en, co) && !co.list().has(en)))
+    }
+
+    /// Also requires the entity to pass a check against one of the manager's component groups.
+    pub fn in_group<G, F>(self, check: F) -> Aspect<T>
+        where T: HasGroup<G>, F: Fn(&G, &EntityData<T>) -> bool + Send + Sync + 'static
+    {
+        Aspect(Box::new(move |en, co| (self.0)(en, co) && check(co.group(), en)))
+    }
+
+    /// Also requires a `U` component in the runtime-registered lists.
+    pub fn with_dynamic<U: Component>(self) -> Aspect<T>
+    {
+        Aspect(Box::new(move |en, co| (self.0)(en, co) &&
+            co.__dynamic().and_then(|d| d.list::<U>()).is_some_and(|list| list.has(en))))
+    }
+
+    /// Also requires there to be no `U` component in the runtime-registered lists.
+    pub fn without_dynamic<U: Component>(self) -> Aspect<T>
+    {
+        Aspect(Box::new(move |en, co| (self.0)(en, co) &&
+            !co.__dynamic().and_then(|d| d.list::<U>()).is_some_and(|list| list.has(en))))
+    }
+
+    /// Also requires a component in the runtime-registered list called `name`.
+    pub fn with_named(self, name: &str) -> Aspect<T>
+    {
+        let name = name.to_string();
+        Aspect(Box::new(move |en, co| (self.0)(en, co) && co.__dynamic().is_some_and(|d| d.has_named(&name, en))))
+    }
+
+    /// Also requires there to be no component in the runtime-registered list called `name`.
+    pub fn without_named(self, name: &str) -> Aspect<T>
+    {
+        let name = name.to_string();
+        Aspect(Box::new(move |en, co| (self.0)(en, co) && !co.__dynamic().is_some_and(|d| d.has_named(&name, en))))
+    }
+
     pub fn check<'a>(&self, entity: &EntityData<'a, T>, components: &T) -> bool
     {
         (self.0)(entity, components)
@@ -2,37 +2,233 @@
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealError, CerealResult};
 #[cfg(feature="serialisation")] use std::io::{Read, Write};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::marker::PhantomData;
-use std::ops::{Index, IndexMut};
+use std::mem;
+use std::ops::{Index, IndexMut, RangeBounds};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 use vec_map::VecMap;
 
 use self::InnerComponentList::{Hot, Cold};
 
 use {BuildData, EditData, ModifyData};
-use {IndexedEntity};
+use {Entity, IndexedEntity};
+use entity::{EntityMap, MapEntities};
 use ComponentManager;
+use delta::ListDelta;
+#[cfg(feature="serialisation")] use replication::FieldChange;
 
 pub trait Component: 'static {}
 
 impl<T:'static> Component for T {}
 
-pub struct ComponentList<C: ComponentManager, T: Component>(InnerComponentList<T>, PhantomData<C>);
+/// Component managers with a list of `T` components.
+///
+/// Bounding on this instead of naming a concrete manager lets a system work with any
+/// manager that has the components it needs. `#[derive(ComponentManager)]` from `ecs_derive`
+/// implements it, and `components!` does too when given `#[has_component]`. That option needs
+/// every list in the manager to hold a different type, otherwise the impls conflict.
+pub trait HasComponent<T: Component>: ComponentManager
+{
+    fn list(&self) -> &ComponentList<Self, T>;
+    fn list_mut(&mut self) -> &mut ComponentList<Self, T>;
+}
 
+pub struct ComponentList<C: ComponentManager, T: Component>
+{
+    storage: InnerComponentList<T>,
+    hooks: Hooks<T>,
+    /// Present for lists created with `hot_indexed` or `cold_indexed`.
+    index: Option<ValueIndex<T>>,
+    /// Present once something has asked which components have changed.
+    changes: Mutex<Option<Changes>>,
+    marker: PhantomData<C>,
+}
+
+#[derive(Clone)]
 enum InnerComponentList<T: Component>
 {
     Hot(VecMap<T>),
     Cold(HashMap<usize, T>),
 }
 
+type Hook<T> = Option<Arc<dyn Fn(Entity, &T) + Send + Sync>>;
+type ReplaceHook<T> = Option<Arc<dyn Fn(Entity, &T, &T) + Send + Sync>>;
+
+/// Callbacks run when a list's components are added, replaced or removed.
+struct Hooks<T: Component>
+{
+    add: Hook<T>,
+    replace: ReplaceHook<T>,
+    remove: Hook<T>,
+}
+
+impl<T: Component> Default for Hooks<T>
+{
+    fn default() -> Hooks<T>
+    {
+        Hooks { add: None, replace: None, remove: None }
+    }
+}
+
+impl<T: Component> Clone for Hooks<T>
+{
+    fn clone(&self) -> Hooks<T>
+    {
+        Hooks {
+            add: self.add.clone(),
+            replace: self.replace.clone(),
+            remove: self.remove.clone(),
+        }
+    }
+}
+
+/// The entity behind each component of an indexed list, and which components have changed
+/// since their values were last indexed.
+///
+/// The values themselves need `T: Ord`, so they're only indexed when the list is searched.
+struct ValueIndex<T>
+{
+    entities: BTreeMap<usize, Entity>,
+    dirty: Mutex<BTreeSet<usize>>,
+    values: Mutex<Values<T>>,
+}
+
+impl<T> ValueIndex<T>
+{
+    fn new() -> ValueIndex<T>
+    {
+        ValueIndex {
+            entities: BTreeMap::new(),
+            dirty: Mutex::new(BTreeSet::new()),
+            values: Mutex::new(Values { entities: BTreeMap::new(), current: HashMap::new() }),
+        }
+    }
+
+    fn changed(&mut self, index: usize)
+    {
+        self.dirty.get_mut().unwrap().insert(index);
+    }
+}
+
+impl<T> Clone for ValueIndex<T>
+{
+    fn clone(&self) -> ValueIndex<T>
+    {
+        // The values are indexed again when the copy is first searched.
+        let mut index = ValueIndex::new();
+        index.entities = self.entities.clone();
+        *index.dirty.get_mut().unwrap() = self.entities.keys().cloned().collect();
+        index
+    }
+}
+
+/// When each component last changed, for finding what's changed since a reader last looked.
+///
+/// Versions come from a counter shared by every list, so a list that replaces another (when a
+/// snapshot is restored, say) counts everything as changed for readers of the old one.
+struct Changes
+{
+    since: u64,
+    changed: VecMap<u64>,
+}
+
+static VERSION: AtomicU64 = AtomicU64::new(0);
+
+impl Changes
+{
+    fn new() -> Changes
+    {
+        Changes {
+            since: VERSION.fetch_add(1, Ordering::Relaxed) + 1,
+            changed: VecMap::new(),
+        }
+    }
+
+    fn changed(&mut self, index: usize)
+    {
+        self.changed.insert(index, VERSION.fetch_add(1, Ordering::Relaxed) + 1);
+    }
+}
+
+struct Values<T>
+{
+    entities: BTreeMap<T, BTreeSet<Entity>>,
+    current: HashMap<usize, (Entity, T)>,
+}
+
+/// Looks up the entities in an indexed list by their component's value, from
+/// `ComponentList::search`.
+pub struct Search<'a, C: ComponentManager + 'a, T: Component + 'a>(&'a ComponentList<C, T>);
+
+impl<'a, C: ComponentManager, T: Component + Ord + Clone> Search<'a, C, T>
+{
+    /// The entities whose component equals `value`, in the order they were created.
+    pub fn find(&self, value: &T) -> Vec<Entity>
+    {
+        self.0.with_values(|values| values.entities.get(value).map_or_else(Vec::new, |set| set.iter().cloned().collect()))
+    }
+
+    /// The first entity whose component equals `value`, for components that identify an entity.
+    pub fn find_first(&self, value: &T) -> Option<Entity>
+    {
+        self.0.with_values(|values| values.entities.get(value).and_then(|set| set.iter().next().cloned()))
+    }
+
+    /// The entities whose component falls within `range`, ordered by value.
+    pub fn find_range<R: RangeBounds<T>>(&self, range: R) -> Vec<Entity>
+    {
+        self.0.with_values(|values| values.entities.range(range).flat_map(|(_, set)| set.iter().cloned()).collect())
+    }
+}
+
+impl<C: ComponentManager, T: Component> Clone for ComponentList<C, T> where T: Clone {
+    fn clone(&self) -> ComponentList<C, T> {
+        ComponentList {
+            storage: self.storage.clone(),
+            hooks: self.hooks.clone(),
+            index: self.index.clone(),
+            changes: Mutex::new(self.changes.lock().unwrap().as_ref().map(|_| Changes::new())),
+            marker: PhantomData,
+        }
+    }
+}
+
 #[cfg(feature="serialisation")]
 unsafe impl<C: ComponentManager, T: Component> CerealData for ComponentList<C, T> where T: CerealData {
     fn write(&self, w: &mut Write) -> CerealResult<()> {
-        self.0.write(w)
+        try!(self.storage.write(w));
+        match self.index {
+            Some(ref index) => {
+                try!(1u8.write(w));
+                try!((index.entities.len() as u64).write(w));
+                for (&idx, entity) in &index.entities {
+                    try!((idx as u64).write(w));
+                    try!(entity.write(w));
+                }
+            },
+            None => try!(0u8.write(w)),
+        }
+        Ok(())
     }
 
     fn read(r: &mut Read) -> CerealResult<Self> {
-        CerealData::read(r).map(|inner| ComponentList(inner, PhantomData))
+        let inner = try!(CerealData::read(r));
+        let index = match try!(u8::read(r)) {
+            0 => None,
+            1 => {
+                let mut index = ValueIndex::new();
+                for _ in 0..try!(u64::read(r)) {
+                    let idx = try!(u64::read(r)) as usize;
+                    index.entities.insert(idx, try!(CerealData::read(r)));
+                    index.changed(idx);
+                }
+                Some(index)
+            },
+            x => return Err(CerealError::Msg(format!("Unrecognized index flag (None = 0, Some = 1, Found {:?})", x))),
+        };
+        Ok(ComponentList::new(inner, index))
     }
 }
 
@@ -51,6 +247,9 @@ unsafe impl<T: Component> CerealData for InnerComponentList<T> where T: CerealDa
             Cold(ref list) => {
                 try!(2u8.write(w));
                 try!((list.len() as u64).write(w));
+                // Sorted so that the same components are always written the same way.
+                let mut list: Vec<_> = list.iter().collect();
+                list.sort_by_key(|&(idx, _)| *idx);
                 for (idx, data) in list {
                     try!((*idx as u64).write(w));
                     try!(data.write(w));
@@ -86,55 +285,120 @@ unsafe impl<T: Component> CerealData for InnerComponentList<T> where T: CerealDa
 
 impl<C: ComponentManager, T: Component> ComponentList<C, T>
 {
+    fn new(storage: InnerComponentList<T>, index: Option<ValueIndex<T>>) -> ComponentList<C, T>
+    {
+        ComponentList {
+            storage,
+            hooks: Hooks::default(),
+            index,
+            changes: Mutex::new(None),
+            marker: PhantomData,
+        }
+    }
+
     pub fn hot() -> ComponentList<C, T>
     {
-        ComponentList(Hot(VecMap::new()), PhantomData)
+        ComponentList::new(Hot(VecMap::new()), None)
     }
 
     pub fn cold() -> ComponentList<C, T>
     {
-        ComponentList(Cold(HashMap::new()), PhantomData)
+        ComponentList::new(Cold(HashMap::new()), None)
     }
 
-    pub fn add(&mut self, entity: &BuildData<C>, component: T) -> Option<T>
+    /// A hot list which can be searched by value with `search`.
+    ///
+    /// Components changed through `borrow` or indexing are indexed again on the next search.
+    pub fn hot_indexed() -> ComponentList<C, T>
     {
-        match self.0
-        {
-            Hot(ref mut c) => c.insert(entity.0.index(), component),
-            Cold(ref mut c) => c.insert(entity.0.index(), component),
+        ComponentList::new(Hot(VecMap::new()), Some(ValueIndex::new()))
+    }
+
+    /// A cold list which can be searched by value with `search`.
+    pub fn cold_indexed() -> ComponentList<C, T>
+    {
+        ComponentList::new(Cold(HashMap::new()), Some(ValueIndex::new()))
+    }
+
+    pub fn is_indexed(&self) -> bool
+    {
+        self.index.is_some()
+    }
+
+    /// Searches the list by value, or `None` if it wasn't created with `hot_indexed` or
+    /// `cold_indexed`.
+    pub fn search(&self) -> Option<Search<'_, C, T>> where T: Ord + Clone
+    {
+        if self.is_indexed() {
+            Some(Search(self))
+        } else {
+            None
         }
     }
 
+    /// Runs `hook` whenever an entity without this component is given one.
+    ///
+    /// Hooks run after the list has been changed, for every change except those made through
+    /// `borrow` or indexing. They're shared with any copies of the list, such as in snapshots,
+    /// but aren't run when a world is restored or loaded. They have to be `Send` and `Sync` so
+    /// that the list still is.
+    pub fn on_add<F>(&mut self, hook: F) where F: Fn(Entity, &T) + Send + Sync + 'static
+    {
+        self.hooks.add = Some(Arc::new(hook));
+    }
+
+    /// Runs `hook` with the old and new components whenever an entity's component is replaced.
+    pub fn on_replace<F>(&mut self, hook: F) where F: Fn(Entity, &T, &T) + Send + Sync + 'static
+    {
+        self.hooks.replace = Some(Arc::new(hook));
+    }
+
+    /// Runs `hook` with the old component whenever one is removed, including when its entity is.
+    pub fn on_remove<F>(&mut self, hook: F) where F: Fn(Entity, &T) + Send + Sync + 'static
+    {
+        self.hooks.remove = Some(Arc::new(hook));
+    }
+
+    /// Removes the hooks from this list.
+    pub fn clear_hooks(&mut self)
+    {
+        self.hooks = Hooks::default();
+    }
+
+    pub fn add(&mut self, entity: &BuildData<C>, component: T) -> Option<T>
+    {
+        self.hooked_insert(**entity.0, entity.0.index(), component)
+    }
+
     pub fn insert(&mut self, entity: &ModifyData<C>, component: T) -> Option<T>
     {
-        match self.0
-        {
-            Hot(ref mut c) => c.insert(entity.entity().index(), component),
-            Cold(ref mut c) => c.insert(entity.entity().index(), component),
-        }
+        self.hooked_insert(**entity.entity(), entity.entity().index(), component)
     }
 
     pub fn remove(&mut self, entity: &ModifyData<C>) -> Option<T>
     {
-        match self.0
+        self.hooked_remove(**entity.entity(), entity.entity().index())
+    }
+
+    /// Applies a `Change` to an entity's component, returning the component it replaced.
+    pub fn apply(&mut self, entity: &ModifyData<C>, change: Change<T>) -> Option<T>
+    {
+        match change
         {
-            Hot(ref mut c) => c.remove(&entity.entity().index()),
-            Cold(ref mut c) => c.remove(&entity.entity().index()),
+            Change::Unchanged => None,
+            Change::Set(component) => self.insert(entity, component),
+            Change::Remove => self.remove(entity),
         }
     }
 
     pub fn set<U: EditData<C>>(&mut self, entity: &U, component: T) -> Option<T>
     {
-        match self.0
-        {
-            Hot(ref mut c) => c.insert(entity.entity().index(), component),
-            Cold(ref mut c) => c.insert(entity.entity().index(), component),
-        }
+        self.hooked_insert(**entity.entity(), entity.entity().index(), component)
     }
 
     pub fn get<U: EditData<C>>(&self, entity: &U) -> Option<T> where T: Clone
     {
-        match self.0
+        match self.storage
         {
             Hot(ref c) => c.get(&entity.entity().index()).cloned(),
             Cold(ref c) => c.get(&entity.entity().index()).cloned(),
@@ -143,7 +407,7 @@ impl<C: ComponentManager, T: Component> ComponentList<C, T>
 
     pub fn has<U: EditData<C>>(&self, entity: &U) -> bool
     {
-        match self.0
+        match self.storage
         {
             Hot(ref c) => c.contains_key(&entity.entity().index()),
             Cold(ref c) => c.contains_key(&entity.entity().index()),
@@ -152,7 +416,8 @@ impl<C: ComponentManager, T: Component> ComponentList<C, T>
 
     pub fn borrow<U: EditData<C>>(&mut self, entity: &U) -> Option<&mut T>
     {
-        match self.0
+        self.touch(entity.entity().index());
+        match self.storage
         {
             Hot(ref mut c) => c.get_mut(&entity.entity().index()),
             Cold(ref mut c) => c.get_mut(&entity.entity().index()),
@@ -161,11 +426,231 @@ impl<C: ComponentManager, T: Component> ComponentList<C, T>
 
     pub fn __clear(&mut self, entity: &IndexedEntity<C>)
     {
-        match self.0
+        self.hooked_remove(**entity, entity.index());
+    }
+
+    /// Updates the entity's component to refer to moved entities by their new identifiers.
+    pub fn map_entities<U: EditData<C>>(&mut self, entity: &U, map: &EntityMap) where T: MapEntities
+    {
+        if let Some(component) = self.borrow(entity) {
+            component.map_entities(map);
+        }
+    }
+
+    /// Moves `from`'s component in `other`, if it has one, to `to` in this list.
+    #[doc(hidden)]
+    pub fn __move_entity(&mut self, other: &mut ComponentList<C, T>, from: &IndexedEntity<C>, to: &IndexedEntity<C>)
+    {
+        if let Some(component) = other.hooked_remove(**from, from.index()) {
+            self.hooked_insert(**to, to.index(), component);
+        }
+    }
+
+    /// An empty list with the same kind of storage.
+    #[doc(hidden)]
+    pub fn __empty_like(&self) -> ComponentList<C, T>
+    {
+        match (&self.storage, self.is_indexed())
         {
-            Hot(ref mut c) => c.remove(&entity.index()),
-            Cold(ref mut c) => c.remove(&entity.index()),
-        };
+            (&Hot(_), false) => ComponentList::hot(),
+            (&Cold(_), false) => ComponentList::cold(),
+            (&Hot(_), true) => ComponentList::hot_indexed(),
+            (&Cold(_), true) => ComponentList::cold_indexed(),
+        }
+    }
+
+    /// Replaces the list's contents with a copy of `snapshot`'s, keeping this list's hooks.
+    #[doc(hidden)]
+    pub fn __restore(&mut self, snapshot: &ComponentList<C, T>) where T: Clone
+    {
+        let hooks = ::std::mem::replace(self, snapshot.clone()).hooks;
+        self.hooks = hooks;
+    }
+
+    /// Copies `from`'s component, if it has one, to `to`.
+    #[doc(hidden)]
+    pub fn __clone_entity(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>) where T: Clone
+    {
+        if let Some(component) = self.__get(from.index()).cloned() {
+            self.hooked_insert(**to, to.index(), component);
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __get(&self, index: usize) -> Option<&T>
+    {
+        match self.storage
+        {
+            Hot(ref c) => c.get(&index),
+            Cold(ref c) => c.get(&index),
+        }
+    }
+
+    /// Stores a component without running any hooks.
+    fn raw_insert(&mut self, index: usize, component: T) -> Option<T>
+    {
+        match self.storage
+        {
+            Hot(ref mut c) => c.insert(index, component),
+            Cold(ref mut c) => c.insert(index, component),
+        }
+    }
+
+    /// Removes a component without running any hooks.
+    fn raw_remove(&mut self, index: usize) -> Option<T>
+    {
+        match self.storage
+        {
+            Hot(ref mut c) => c.remove(&index),
+            Cold(ref mut c) => c.remove(&index),
+        }
+    }
+
+    fn hooked_insert(&mut self, entity: Entity, index: usize, component: T) -> Option<T>
+    {
+        if let Some(ref mut values) = self.index {
+            values.entities.insert(index, entity);
+        }
+        self.touch(index);
+        let old = self.raw_insert(index, component);
+        let new = self.__get(index).unwrap();
+        match old {
+            Some(ref old) => if let Some(ref hook) = self.hooks.replace { hook(entity, old, new) },
+            None => if let Some(ref hook) = self.hooks.add { hook(entity, new) },
+        }
+        old
+    }
+
+    fn hooked_remove(&mut self, entity: Entity, index: usize) -> Option<T>
+    {
+        if let Some(ref mut values) = self.index {
+            values.entities.remove(&index);
+        }
+        self.touch(index);
+        let old = self.raw_remove(index);
+        if let (Some(old), Some(hook)) = (old.as_ref(), self.hooks.remove.as_ref()) {
+            hook(entity, old);
+        }
+        old
+    }
+
+    /// Notes that the component at `index` may have changed.
+    fn touch(&mut self, index: usize)
+    {
+        if let Some(ref mut values) = self.index {
+            values.changed(index);
+        }
+        if let Some(ref mut changes) = *self.changes.get_mut().unwrap() {
+            changes.changed(index);
+        }
+    }
+
+    /// Starts recording when each component changes, if the list wasn't already, and returns
+    /// the version to pass to `__changed_since` to find what changes after this point.
+    #[doc(hidden)]
+    pub fn __track_changes(&self) -> u64
+    {
+        let mut changes = self.changes.lock().unwrap();
+        if changes.is_none() {
+            *changes = Some(Changes::new());
+        }
+        VERSION.load(Ordering::Relaxed)
+    }
+
+    /// Whether the component at `index` may have changed since `__track_changes` returned
+    /// `version`.
+    #[doc(hidden)]
+    pub fn __changed_since(&self, index: usize, version: u64) -> bool
+    {
+        match *self.changes.lock().unwrap() {
+            Some(ref changes) => version < changes.since || changes.changed.get(&index).is_some_and(|&v| v > version),
+            None => true,
+        }
+    }
+
+    /// Brings the value index up to date, and then calls `f` with it.
+    ///
+    /// Only called through `Search`, which is only given out for indexed lists.
+    fn with_values<F, R>(&self, f: F) -> R where F: FnOnce(&Values<T>) -> R, T: Ord + Clone
+    {
+        let index = self.index.as_ref().unwrap();
+        let mut values = index.values.lock().unwrap();
+        for i in mem::take(&mut *index.dirty.lock().unwrap()) {
+            if let Some((entity, old)) = values.current.remove(&i) {
+                let now_empty = values.entities.get_mut(&old).is_some_and(|set| { set.remove(&entity); set.is_empty() });
+                if now_empty {
+                    values.entities.remove(&old);
+                }
+            }
+            if let (Some(&entity), Some(value)) = (index.entities.get(&i), self.__get(i)) {
+                values.entities.entry(value.clone()).or_default().insert(entity);
+                values.current.insert(i, (entity, value.clone()));
+            }
+        }
+        f(&values)
+    }
+
+    /// Compares this list against `old` for each `(entity, old index, new index)` triple.
+    #[doc(hidden)]
+    pub fn __diff(&self, old: &ComponentList<C, T>, entities: &[(Entity, Option<usize>, usize)]) -> ListDelta<T>
+        where T: Clone + PartialEq
+    {
+        let mut delta = ListDelta::new();
+        for &(entity, old_index, new_index) in entities {
+            let previous = old_index.and_then(|i| old.__get(i));
+            match (previous, self.__get(new_index)) {
+                (None, Some(current)) => delta.added.push((entity, current.clone())),
+                (Some(previous), Some(current)) => if previous != current {
+                    delta.changed.push((entity, current.clone()))
+                },
+                (Some(_), None) => delta.removed.push(entity),
+                (None, None) => (),
+            }
+        }
+        delta
+    }
+
+    #[cfg(feature="serialisation")]
+    #[doc(hidden)]
+    pub fn __encode(&self, index: usize) -> CerealResult<Option<Vec<u8>>> where T: CerealData
+    {
+        match self.__get(index) {
+            Some(component) => {
+                let mut bytes = Vec::new();
+                try!(component.write(&mut bytes));
+                Ok(Some(bytes))
+            },
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(feature="serialisation")]
+    #[doc(hidden)]
+    pub fn __decode(&mut self, entity: &IndexedEntity<C>, change: &FieldChange) -> CerealResult<()> where T: CerealData
+    {
+        match *change {
+            FieldChange::Unchanged => (),
+            FieldChange::Set(ref bytes) => { self.hooked_insert(**entity, entity.index(), try!(CerealData::read(&mut &bytes[..]))); },
+            FieldChange::Removed => { self.hooked_remove(**entity, entity.index()); },
+        }
+        Ok(())
+    }
+
+    /// Applies a delta, using `index_of` to find where each affected entity is stored.
+    #[doc(hidden)]
+    pub fn __apply_delta(&mut self, delta: &ListDelta<T>, index_of: &mut dyn FnMut(&Entity) -> Option<usize>)
+        where T: Clone
+    {
+        for &(entity, ref component) in delta.added.iter().chain(delta.changed.iter()) {
+            if let Some(index) = index_of(&entity) {
+                self.hooked_insert(entity, index, component.clone());
+            }
+        }
+        for &entity in &delta.removed {
+            if let Some(index) = index_of(&entity) {
+                self.hooked_remove(entity, index);
+            }
+        }
     }
 }
 
@@ -174,7 +659,7 @@ impl<C: ComponentManager, T: Component, U: EditData<C>> Index<U> for ComponentLi
     type Output = T;
     fn index(&self, en: U) -> &T
     {
-        match self.0
+        match self.storage
         {
             Hot(ref c) => &c[en.entity().index()],
             Cold(ref c) => &c[&en.entity().index()],
@@ -186,7 +671,8 @@ impl<C: ComponentManager, T: Component, U: EditData<C>> IndexMut<U> for Componen
 {
     fn index_mut(&mut self, en: U) -> &mut T
     {
-        match self.0
+        self.touch(en.entity().index());
+        match self.storage
         {
             Hot(ref mut c) => c.get_mut(&en.entity().index()),
             Cold(ref mut c) => c.get_mut(&en.entity().index()),
@@ -194,6 +680,16 @@ impl<C: ComponentManager, T: Component, U: EditData<C>> IndexMut<U> for Componen
     }
 }
 
+/// A change to a single component of an entity, as held by generated modifiers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Change<T>
+{
+    #[default]
+    Unchanged,
+    Set(T),
+    Remove,
+}
+
 pub trait EntityBuilder<T: ComponentManager>
 {
     fn build<'a>(self, BuildData<'a, T>, &mut T);
@@ -0,0 +1,97 @@
+
+//! Differences between two states of a world.
+//!
+//! A `Delta` is computed with `DataHelper::diff` or `DataHelper::changes_since`, and can be
+//! applied to any world which was in the old state with `World::apply_delta`.
+//!
+//! Entities are matched by their identifier, so deltas only make sense between states that
+//! share a history (eg: a world and an earlier snapshot of it). Services aren't included.
+
+use ComponentManager;
+use Entity;
+use plugin::ComponentGroup;
+
+/// Changes to a single `ComponentList`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListDelta<T>
+{
+    /// Components present in the new state but not the old one.
+    pub added: Vec<(Entity, T)>,
+    /// Components present in both states, but with different values.
+    pub changed: Vec<(Entity, T)>,
+    /// Entities that lost the component. Doesn't include entities that were removed entirely.
+    pub removed: Vec<Entity>,
+}
+
+impl<T> ListDelta<T>
+{
+    pub fn new() -> ListDelta<T>
+    {
+        ListDelta {
+            added: Vec::new(),
+            changed: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<T> Default for ListDelta<T>
+{
+    fn default() -> ListDelta<T>
+    {
+        ListDelta::new()
+    }
+}
+
+/// Component managers that can be compared and patched.
+///
+/// Implemented by `components!` whenever every component type is `Clone + PartialEq`.
+pub trait DiffComponents: ComponentManager
+{
+    /// A tuple holding a `ListDelta` for each component list in declaration order, followed by
+    /// the delta of each component group.
+    type Delta;
+    #[doc(hidden)]
+    fn __diff(&self, old: &Self, entities: &[(Entity, Option<usize>, usize)]) -> Self::Delta;
+    #[doc(hidden)]
+    fn __apply(&mut self, delta: &Self::Delta, index_of: &mut dyn FnMut(&Entity) -> Option<usize>);
+}
+
+/// Component groups that can be compared and patched, like `DiffComponents`.
+pub trait DiffGroup<C: ComponentManager>: ComponentGroup<C>
+{
+    /// A tuple holding a `ListDelta` for each component list, in declaration order.
+    type Delta;
+    #[doc(hidden)]
+    fn __diff(&self, old: &Self, entities: &[(Entity, Option<usize>, usize)]) -> Self::Delta;
+    #[doc(hidden)]
+    fn __apply(&mut self, delta: &Self::Delta, index_of: &mut dyn FnMut(&Entity) -> Option<usize>);
+}
+
+/// The difference between two states of a world.
+pub struct Delta<C: DiffComponents>
+{
+    /// Entities that exist in the new state but not the old one.
+    pub created: Vec<Entity>,
+    /// Entities that exist in the old state but not the new one.
+    pub removed: Vec<Entity>,
+    /// Changes to each component list.
+    pub components: C::Delta,
+}
+
+impl<C: DiffComponents> Clone for Delta<C> where C::Delta: Clone
+{
+    fn clone(&self) -> Delta<C>
+    {
+        Delta {
+            created: self.created.clone(),
+            removed: self.removed.clone(),
+            components: self.components.clone(),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+
+//! Component lists registered at runtime, such as those added by mods or scripts.
+//!
+//! A component manager opts in by holding a `DynamicComponents` in a field marked
+//! `#[dynamic(name)]` before the struct in `components!` (or `#[dynamic]` on the field with
+//! `#[derive(ComponentManager)]`):
+//!
+//! ```ignore
+//! components! {
+//!     #[dynamic(extra)]
+//!     struct MyComponents {
+//!         #[hot] position: Position,
+//!     }
+//! }
+//!
+//! world.extra.register("health", ComponentList::<MyComponents, Health>::cold());
+//! ```
+//!
+//! Registered lists are used like any other, through `list::<T>()` and `list_mut::<T>()`, and
+//! can be checked by aspects with `Aspect::with_dynamic` and `Aspect::with_named`. Their
+//! components are removed and cloned along with the entity, but aren't included in deltas.
+//!
+//! As with any other component, systems only check an entity against their aspect when it's
+//! created or modified, so lists should be registered before the entities that use them.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use {ComponentList, ComponentManager, EditData, IndexedEntity, ModifyData};
+use component::Component;
+
+trait AnyList<C: ComponentManager>: Send + Sync
+{
+    fn has(&self, index: usize) -> bool;
+    fn clear(&mut self, entity: &IndexedEntity<C>);
+    fn clone_entity(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>);
+    fn move_entity(&mut self, other: &mut dyn AnyList<C>, from: &IndexedEntity<C>, to: &IndexedEntity<C>);
+    fn restore(&mut self, snapshot: &dyn AnyList<C>);
+    fn boxed_empty(&self) -> Box<dyn AnyList<C>>;
+    fn boxed_clone(&self) -> Box<dyn AnyList<C>>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<C: ComponentManager, T: Component + Clone> AnyList<C> for ComponentList<C, T> where ComponentList<C, T>: Send + Sync
+{
+    fn has(&self, index: usize) -> bool
+    {
+        self.__get(index).is_some()
+    }
+
+    fn clear(&mut self, entity: &IndexedEntity<C>)
+    {
+        self.__clear(entity);
+    }
+
+    fn clone_entity(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>)
+    {
+        self.__clone_entity(from, to);
+    }
+
+    fn move_entity(&mut self, other: &mut dyn AnyList<C>, from: &IndexedEntity<C>, to: &IndexedEntity<C>)
+    {
+        if let Some(other) = other.as_any_mut().downcast_mut() {
+            self.__move_entity(other, from, to);
+        }
+    }
+
+    fn restore(&mut self, snapshot: &dyn AnyList<C>)
+    {
+        if let Some(snapshot) = snapshot.as_any().downcast_ref() {
+            self.__restore(snapshot);
+        }
+    }
+
+    fn boxed_empty(&self) -> Box<dyn AnyList<C>>
+    {
+        Box::new(self.__empty_like())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn AnyList<C>>
+    {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+}
+
+/// A registry of component lists, keyed by component type and by name.
+pub struct DynamicComponents<C: ComponentManager>
+{
+    lists: HashMap<TypeId, Box<dyn AnyList<C>>>,
+    names: HashMap<String, TypeId>,
+}
+
+impl<C: ComponentManager> DynamicComponents<C>
+{
+    pub fn new() -> DynamicComponents<C>
+    {
+        DynamicComponents {
+            lists: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Registers a list for `T` components, returning false if there already was one.
+    ///
+    /// Components must be `Clone` so that snapshots can copy them, and `Send` and `Sync` so that
+    /// the component manager still is.
+    pub fn register<T: Component + Clone>(&mut self, name: &str, list: ComponentList<C, T>) -> bool
+        where ComponentList<C, T>: Send + Sync
+    {
+        let id = TypeId::of::<T>();
+        if self.lists.contains_key(&id) || self.names.contains_key(name) {
+            return false;
+        }
+        self.lists.insert(id, Box::new(list));
+        self.names.insert(name.to_string(), id);
+        true
+    }
+
+    pub fn is_registered<T: Component>(&self) -> bool
+    {
+        self.lists.contains_key(&TypeId::of::<T>())
+    }
+
+    /// The names of every registered list, in no particular order.
+    pub fn names(&self) -> Vec<&str>
+    {
+        self.names.keys().map(|name| &name[..]).collect()
+    }
+
+    /// The type of the components registered under a name.
+    pub fn type_of(&self, name: &str) -> Option<TypeId>
+    {
+        self.names.get(name).cloned()
+    }
+
+    pub fn list<T: Component>(&self) -> Option<&ComponentList<C, T>>
+    {
+        self.lists.get(&TypeId::of::<T>()).and_then(|list| list.as_any().downcast_ref())
+    }
+
+    pub fn list_mut<T: Component>(&mut self) -> Option<&mut ComponentList<C, T>>
+    {
+        self.lists.get_mut(&TypeId::of::<T>()).and_then(|list| list.as_any_mut().downcast_mut())
+    }
+
+    /// Whether the entity has a component in the list registered under `name`.
+    pub fn has_named<U: EditData<C>>(&self, name: &str, entity: &U) -> bool
+    {
+        self.names.get(name)
+            .and_then(|id| self.lists.get(id))
+            .is_some_and(|list| list.has(entity.entity().index()))
+    }
+
+    /// Removes the entity's component from the list registered under `name`, returning false if
+    /// there was no such list.
+    pub fn remove_named(&mut self, name: &str, entity: &ModifyData<C>) -> bool
+    {
+        let lists = &mut self.lists;
+        match self.names.get(name).and_then(|id| lists.get_mut(id))
+        {
+            Some(list) => {
+                list.clear(entity.entity());
+                true
+            },
+            None => false,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __clear(&mut self, entity: &IndexedEntity<C>)
+    {
+        for list in self.lists.values_mut() {
+            list.clear(entity);
+        }
+    }
+
+    /// Moves an entity's components from `other`, registering any lists this is missing.
+    #[doc(hidden)]
+    pub fn __move_entity(&mut self, other: &mut DynamicComponents<C>, from: &IndexedEntity<C>, to: &IndexedEntity<C>)
+    {
+        for (name, id) in &other.names {
+            let list = match other.lists.get_mut(id) {
+                Some(list) => list,
+                None => continue,
+            };
+            if !self.lists.contains_key(id) {
+                self.lists.insert(*id, list.boxed_empty());
+                self.names.entry(name.clone()).or_insert(*id);
+            }
+            if let Some(target) = self.lists.get_mut(id) {
+                target.move_entity(list.as_mut(), from, to);
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __clone_entity(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>)
+    {
+        for list in self.lists.values_mut() {
+            list.clone_entity(from, to);
+        }
+    }
+
+    /// Replaces each list's contents with a copy of `snapshot`'s, keeping the lists' hooks.
+    ///
+    /// Lists registered since the snapshot was taken stay registered, but are emptied.
+    #[doc(hidden)]
+    pub fn __restore(&mut self, snapshot: &DynamicComponents<C>)
+    {
+        for (id, list) in &mut self.lists {
+            match snapshot.lists.get(id) {
+                Some(old) => list.restore(&**old),
+                None => {
+                    let empty = list.boxed_empty();
+                    list.restore(&*empty);
+                },
+            }
+        }
+        for (id, list) in &snapshot.lists {
+            if !self.lists.contains_key(id) {
+                self.lists.insert(*id, list.boxed_clone());
+            }
+        }
+        for (name, id) in &snapshot.names {
+            self.names.entry(name.clone()).or_insert(*id);
+        }
+    }
+}
+
+impl<C: ComponentManager> Clone for DynamicComponents<C>
+{
+    fn clone(&self) -> DynamicComponents<C>
+    {
+        DynamicComponents {
+            lists: self.lists.iter().map(|(id, list)| (*id, list.boxed_clone())).collect(),
+            names: self.names.clone(),
+        }
+    }
+}
+
+impl<C: ComponentManager> Default for DynamicComponents<C>
+{
+    fn default() -> DynamicComponents<C>
+    {
+        DynamicComponents::new()
+    }
+}
@@ -3,10 +3,13 @@
 
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealError, CerealResult};
 
-use std::collections::hash_map::{HashMap, Values};
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::collections::hash_map::{self, HashMap};
 use std::default::Default;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::vec;
 
 use Aspect;
 use BuildData;
@@ -14,11 +17,12 @@ use ComponentManager;
 use EntityData;
 use EntityBuilder;
 use ServiceManager;
-use SystemManager;
+use world::Listener;
+use names::Names;
 
 pub type Id = u64;
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
 pub struct Entity(Id);
 
 #[cfg(feature="serialisation")]
@@ -86,6 +90,85 @@ impl Default for Entity
     }
 }
 
+/// The new identifier of each entity moved from one world to another, as returned by
+/// `World::merge`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityMap(BTreeMap<Entity, Entity>);
+
+impl EntityMap
+{
+    pub fn new() -> EntityMap
+    {
+        EntityMap(BTreeMap::new())
+    }
+
+    pub fn insert(&mut self, old: Entity, new: Entity) -> Option<Entity>
+    {
+        self.0.insert(old, new)
+    }
+
+    pub fn get(&self, old: &Entity) -> Option<Entity>
+    {
+        self.0.get(old).cloned()
+    }
+
+    /// The entity's new identifier, or the same one if it wasn't moved.
+    pub fn map(&self, entity: Entity) -> Entity
+    {
+        self.get(&entity).unwrap_or(entity)
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.0.is_empty()
+    }
+
+    /// Each `(old, new)` pair, in order of the old identifiers.
+    pub fn iter(&self) -> btree_map::Iter<'_, Entity, Entity>
+    {
+        self.0.iter()
+    }
+}
+
+/// Values that refer to other entities, which need updating when those entities move.
+pub trait MapEntities
+{
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+impl MapEntities for Entity
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        *self = map.map(*self);
+    }
+}
+
+impl<T: MapEntities> MapEntities for Option<T>
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        if let Some(ref mut inner) = *self {
+            inner.map_entities(map);
+        }
+    }
+}
+
+impl<T: MapEntities> MapEntities for Vec<T>
+{
+    fn map_entities(&mut self, map: &EntityMap)
+    {
+        for inner in self {
+            inner.map_entities(map);
+        }
+    }
+}
+
 pub struct FilteredEntityIter<'a, T: ComponentManager>
 {
     inner: EntityIter<'a, T>,
@@ -96,7 +179,11 @@ pub struct FilteredEntityIter<'a, T: ComponentManager>
 // Inner Entity Iterator
 pub enum EntityIter<'a, T: ComponentManager>
 {
-    Map(Values<'a, Entity, IndexedEntity<T>>),
+    Map(hash_map::Values<'a, Entity, IndexedEntity<T>>),
+    /// Iterates in order of entity id, regardless of the map's history.
+    Sorted(btree_map::Values<'a, Entity, IndexedEntity<T>>),
+    /// Entities collected up front, such as after sorting them.
+    Vec(vec::IntoIter<&'a IndexedEntity<T>>),
 }
 
 impl<'a, T: ComponentManager> EntityIter<'a, T>
@@ -112,8 +199,24 @@ impl<'a, T: ComponentManager> EntityIter<'a, T>
     }
 
     pub fn clone(&self) -> Self {
-        let EntityIter::Map(ref values) = *self;
-        EntityIter::Map(values.clone())
+        match *self {
+            EntityIter::Map(ref values) => EntityIter::Map(values.clone()),
+            EntityIter::Sorted(ref values) => EntityIter::Sorted(values.clone()),
+            EntityIter::Vec(ref values) => EntityIter::Vec(values.clone()),
+        }
+    }
+
+    /// Iterates the entities in `map`, in order of id if `sorted` is set.
+    #[doc(hidden)]
+    pub fn __from_map(map: &'a HashMap<Entity, IndexedEntity<T>>, sorted: bool) -> EntityIter<'a, T>
+    {
+        if sorted {
+            let mut entities: Vec<_> = map.values().collect();
+            entities.sort_by_key(|e| e.1);
+            EntityIter::Vec(entities.into_iter())
+        } else {
+            EntityIter::Map(map.values())
+        }
     }
 }
 
@@ -124,7 +227,9 @@ impl<'a, T: ComponentManager> Iterator for EntityIter<'a, T>
     {
         match *self
         {
-            EntityIter::Map(ref mut values) => values.next().map(|x| EntityData(x))
+            EntityIter::Map(ref mut values) => values.next().map(|x| EntityData(x)),
+            EntityIter::Sorted(ref mut values) => values.next().map(|x| EntityData(x)),
+            EntityIter::Vec(ref mut values) => values.next().map(|x| EntityData(x)),
         }
     }
 }
@@ -149,10 +254,41 @@ impl<'a, T: ComponentManager> Iterator for FilteredEntityIter<'a, T>
     }
 }
 
+#[derive(Copy, Clone)]
 enum Event
 {
-    BuildEntity(Entity),
-    RemoveEntity(Entity),
+    Build(Entity),
+    Remove(Entity),
+    Reactivate(Entity),
+}
+
+#[cfg(feature="serialisation")]
+unsafe impl CerealData for Event {
+    fn write(&self, write: &mut ::std::io::Write) -> CerealResult<()> {
+        match *self {
+            Event::Build(entity) => {
+                try!(1u8.write(write));
+                entity.write(write)
+            },
+            Event::Remove(entity) => {
+                try!(2u8.write(write));
+                entity.write(write)
+            },
+            Event::Reactivate(entity) => {
+                try!(3u8.write(write));
+                entity.write(write)
+            },
+        }
+    }
+
+    fn read(read: &mut ::std::io::Read) -> CerealResult<Event> {
+        match try!(u8::read(read)) {
+            1 => Ok(Event::Build(try!(CerealData::read(read)))),
+            2 => Ok(Event::Remove(try!(CerealData::read(read)))),
+            3 => Ok(Event::Reactivate(try!(CerealData::read(read)))),
+            x => Err(CerealError::Msg(format!("Unrecognized event type (Build = 1, Remove = 2, Reactivate = 3, Found {:?})", x))),
+        }
+    }
 }
 
 /// Handles creation, activation, and validating of entities.
@@ -163,27 +299,56 @@ pub struct EntityManager<T: ComponentManager>
     entities: HashMap<Entity, IndexedEntity<T>>,
     event_queue: Vec<Event>,
     next_id: Id,
+    names: Names,
+    sorted: bool,
+}
+
+impl<T: ComponentManager> Clone for EntityManager<T>
+{
+    fn clone(&self) -> EntityManager<T>
+    {
+        EntityManager
+        {
+            indices: self.indices.clone(),
+            entities: self.entities.iter().map(|(k, v)| (*k, v.__clone())).collect(),
+            event_queue: self.event_queue.clone(),
+            next_id: self.next_id,
+            names: self.names.clone(),
+            sorted: self.sorted,
+        }
+    }
 }
 
 // TODO: Cleanup
 #[cfg(feature="serialisation")]
 unsafe impl<T: ComponentManager> CerealData for EntityManager<T> {
     fn write(&self, write: &mut ::std::io::Write) -> CerealResult<()> {
-        if self.event_queue.len() != 0 {
-            Err(CerealError::Msg("Please flush events before serialising the world".to_string()))
-        } else {
-            try!(self.indices.write(write));
-            try!(self.entities.write(write));
-            self.next_id.write(write)
+        try!(self.indices.write(write));
+        try!((self.entities.len() as u64).write(write));
+        // Sorted so that the same entities are always written the same way.
+        for indexed in self.sorted_values() {
+            try!(indexed.1.write(write));
+            try!(indexed.write(write));
         }
+        try!(self.next_id.write(write));
+        try!(self.event_queue.write(write));
+        self.names.write(write)
     }
 
     fn read(read: &mut ::std::io::Read) -> CerealResult<EntityManager<T>> {
+        let indices = try!(CerealData::read(read));
+        let len = try!(u64::read(read)) as usize;
+        let mut entities = HashMap::with_capacity(len);
+        for _ in 0..len {
+            entities.insert(try!(CerealData::read(read)), try!(CerealData::read(read)));
+        }
         Ok(EntityManager {
-            indices: try!(CerealData::read(read)),
-            entities: try!(CerealData::read(read)),
+            indices: indices,
+            entities: entities,
             next_id: try!(CerealData::read(read)),
-            event_queue: Vec::new(),
+            event_queue: try!(CerealData::read(read)),
+            names: try!(CerealData::read(read)),
+            sorted: false,
         })
     }
 }
@@ -199,48 +364,114 @@ impl<T: ComponentManager> EntityManager<T>
             entities: HashMap::new(),
             next_id: 0,
             event_queue: Vec::new(),
+            names: Names::new(),
+            sorted: false,
         }
     }
 
     pub fn flush_queue<M, S>(&mut self, c: &mut T, m: &mut M, s: &mut S)
-    where M: ServiceManager, S: SystemManager<Components=T, Services=M>
+    where M: ServiceManager, S: Listener<T, M>
     {
         let queue = ::std::mem::replace(&mut self.event_queue, Vec::new());
         for e in queue {
             match e {
-                Event::BuildEntity(entity) => s.__activated(
+                Event::Build(entity) => s.__activate(
                     EntityData(self.indexed(&entity)),
                     c,
                     m
                 ),
-                Event::RemoveEntity(entity) => {
+                Event::Remove(entity) => {
                     {
                         let indexed = self.indexed(&entity);
-                        s.__deactivated(EntityData(indexed), c, m);
+                        s.__deactivate(EntityData(indexed), c, m);
                         c.__remove_all(indexed);
+                        if let Some(dynamic) = c.__dynamic_mut() {
+                            dynamic.__clear(indexed);
+                        }
                     }
                     self.remove(&entity);
                 }
+                Event::Reactivate(entity) => if self.is_valid(&entity) {
+                    s.__reactivate(EntityData(self.indexed(&entity)), c, m);
+                },
+            }
+        }
+    }
+
+    /// Calls `activated` for every entity that isn't still waiting in the queue to be built.
+    ///
+    /// Used to rebuild the systems' state after the entities have been replaced wholesale (eg: loading).
+    /// Entities are always activated in order of id, so that a loaded world is rebuilt the same
+    /// way every time.
+    pub fn activate_all<M, S>(&self, c: &T, m: &mut M, s: &mut S)
+    where M: ServiceManager, S: Listener<T, M>
+    {
+        for entity in self.sorted_values() {
+            if !self.is_queued_for_build(entity) {
+                s.__activate(EntityData(entity), c, m);
             }
         }
     }
 
+    /// Calls `deactivated` for every entity that `activate_all` would have activated.
+    pub fn deactivate_all<M, S>(&self, c: &T, m: &mut M, s: &mut S)
+    where M: ServiceManager, S: Listener<T, M>
+    {
+        for entity in self.sorted_values() {
+            if !self.is_queued_for_build(entity) {
+                s.__deactivate(EntityData(entity), c, m);
+            }
+        }
+    }
+
+    fn is_queued_for_build(&self, entity: &Entity) -> bool
+    {
+        self.event_queue.iter().any(|e| match *e {
+            Event::Build(queued) => queued == *entity,
+            Event::Remove(_) | Event::Reactivate(_) => false,
+        })
+    }
+
     pub fn create_entity<B>(&mut self, builder: B, c: &mut T) -> Entity where B: EntityBuilder<T>
     {
         let entity = self.create();
         builder.build(BuildData(self.indexed(&entity)), c);
-        self.event_queue.push(Event::BuildEntity(entity));
+        self.event_queue.push(Event::Build(entity));
         entity
     }
 
     pub fn remove_entity(&mut self, entity: Entity)
     {
-        self.event_queue.push(Event::RemoveEntity(entity));
+        self.event_queue.push(Event::Remove(entity));
+    }
+
+    /// Queues an entity's systems to be told that its components have changed.
+    pub fn queue_reactivate(&mut self, entity: Entity)
+    {
+        self.event_queue.push(Event::Reactivate(entity));
     }
 
     pub fn iter(&self) -> EntityIter<T>
     {
-        EntityIter::Map(self.entities.values())
+        EntityIter::__from_map(&self.entities, self.sorted)
+    }
+
+    fn sorted_values(&self) -> Vec<&IndexedEntity<T>>
+    {
+        let mut entities: Vec<_> = self.entities.values().collect();
+        entities.sort_by_key(|e| e.1);
+        entities
+    }
+
+    /// Whether entities are iterated in order of id.
+    pub fn is_sorted(&self) -> bool
+    {
+        self.sorted
+    }
+
+    pub fn set_sorted(&mut self, sorted: bool)
+    {
+        self.sorted = sorted;
     }
 
     pub fn count(&self) -> usize
@@ -262,6 +493,27 @@ impl<T: ComponentManager> EntityManager<T>
         ret
     }
 
+    /// Creates an entity with a specific identifier, assigning it the first available index.
+    ///
+    /// Returns false if the identifier is already in use. The entity isn't queued to be built.
+    pub fn create_with_id(&mut self, entity: Entity) -> bool
+    {
+        if self.is_valid(&entity) {
+            return false;
+        }
+        if entity.0 > self.next_id {
+            self.next_id = entity.0;
+        }
+        self.entities.insert(entity, IndexedEntity(self.indices.get_index(), entity, PhantomData));
+        true
+    }
+
+    /// Queues an entity created with `create` or `create_with_id` to be activated.
+    pub fn queue_build(&mut self, entity: Entity)
+    {
+        self.event_queue.push(Event::Build(entity));
+    }
+
     /// Returns true if an entity is valid (not removed from the manager).
     #[inline]
     pub fn is_valid(&self, entity: &Entity) -> bool
@@ -269,13 +521,25 @@ impl<T: ComponentManager> EntityManager<T>
         self.entities.contains_key(entity)
     }
 
-    /// Deletes an entity from the manager.
+    /// Deletes an entity from the manager, along with its name and tags.
     pub fn remove(&mut self, entity: &Entity)
     {
         self.entities.remove(entity).map(|e| self.indices.return_id(e.index()));
+        self.names.remove(entity);
+    }
+
+    pub fn names(&self) -> &Names
+    {
+        &self.names
+    }
+
+    pub fn names_mut(&mut self) -> &mut Names
+    {
+        &mut self.names
     }
 }
 
+#[derive(Clone)]
 struct IndexPool
 {
     recycled: Vec<usize>,
@@ -0,0 +1,173 @@
+
+//! Named sets of entities, kept up to date by the world.
+//!
+//! When several systems share an aspect, each `EntitySystem` keeps its own copy of which
+//! entities match. A named group keeps that set once, in the `DataHelper`, where any system (or
+//! code outside of systems) can read it:
+//!
+//! ```ignore
+//! world.add_entity_group("enemies", aspect!(<MyComponents> all: [position, hostile]));
+//!
+//! // Later, from inside a system:
+//! let enemies = data.entity_group("enemies").unwrap();
+//! for e in enemies.iter() {
+//!     data.position[e].x += 1.0;
+//! }
+//! ```
+//!
+//! Groups are handed out as `Arc`s, so they can be held while the rest of the `DataHelper` is
+//! changed. A group that's still held when its membership changes is copied, leaving the holder
+//! with the entities as they were.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use Aspect;
+use {Entity, IndexedEntity, EntityData, EntityIter};
+use {ComponentManager, ServiceManager};
+use world::Listener;
+
+/// The entities matching a group's aspect.
+pub struct EntityGroup<C: ComponentManager>
+{
+    members: BTreeMap<Entity, IndexedEntity<C>>,
+}
+
+impl<C: ComponentManager> EntityGroup<C>
+{
+    /// The members, in order of entity id.
+    pub fn iter(&self) -> EntityIter<'_, C>
+    {
+        EntityIter::Sorted(self.members.values())
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool
+    {
+        self.members.contains_key(entity)
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.members.is_empty()
+    }
+}
+
+impl<C: ComponentManager> Clone for EntityGroup<C>
+{
+    fn clone(&self) -> EntityGroup<C>
+    {
+        EntityGroup {
+            members: self.members.iter().map(|(entity, indexed)| (*entity, indexed.__clone())).collect(),
+        }
+    }
+}
+
+/// The named groups of a world, held in `DataHelper::groups`.
+pub struct EntityGroups<C: ComponentManager>
+{
+    groups: Vec<(String, Aspect<C>, Arc<EntityGroup<C>>)>,
+}
+
+impl<C: ComponentManager> EntityGroups<C>
+{
+    pub fn new() -> EntityGroups<C>
+    {
+        EntityGroups {
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<EntityGroup<C>>>
+    {
+        self.groups.iter().find(|(other, _, _)| other == name).map(|(_, _, group)| group.clone())
+    }
+
+    pub fn contains(&self, name: &str) -> bool
+    {
+        self.groups.iter().any(|(other, _, _)| other == name)
+    }
+
+    /// The names of the groups, in the order they were added.
+    pub fn names(&self) -> Vec<&str>
+    {
+        self.groups.iter().map(|(name, _, _)| &name[..]).collect()
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.groups.is_empty()
+    }
+
+    /// Adds a group of the given entities that match `aspect`, returning the aspect back if the
+    /// name is already taken. Groups are added with `World::add_entity_group`.
+    #[doc(hidden)]
+    pub fn __add(&mut self, name: &str, aspect: Aspect<C>, entities: EntityIter<C>, components: &C) -> Result<(), Aspect<C>>
+    {
+        if self.contains(name) {
+            return Err(aspect);
+        }
+        let members = entities
+            .filter_map(|e| if aspect.check(&e, components) { Some((**e, e.__clone())) } else { None })
+            .collect();
+        self.groups.push((name.to_string(), aspect, Arc::new(EntityGroup { members })));
+        Ok(())
+    }
+
+    /// Removes a group, returning whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool
+    {
+        let len = self.groups.len();
+        self.groups.retain(|(other, _, _)| other != name);
+        self.groups.len() != len
+    }
+}
+
+impl<C: ComponentManager> Default for EntityGroups<C>
+{
+    fn default() -> EntityGroups<C>
+    {
+        EntityGroups::new()
+    }
+}
+
+impl<C: ComponentManager, M: ServiceManager> Listener<C, M> for EntityGroups<C>
+{
+    fn __activate(&mut self, en: EntityData<C>, co: &C, _: &mut M)
+    {
+        for (_, aspect, group) in &mut self.groups {
+            if aspect.check(&en, co) {
+                Arc::make_mut(group).members.insert(**en, en.__clone());
+            }
+        }
+    }
+
+    fn __reactivate(&mut self, en: EntityData<C>, co: &C, _: &mut M)
+    {
+        for (_, aspect, group) in &mut self.groups {
+            match (group.contains(&en), aspect.check(&en, co)) {
+                (false, true) => { Arc::make_mut(group).members.insert(**en, en.__clone()); },
+                (true, false) => { Arc::make_mut(group).members.remove(&**en); },
+                _ => (),
+            }
+        }
+    }
+
+    fn __deactivate(&mut self, en: EntityData<C>, _: &C, _: &mut M)
+    {
+        for (_, _, group) in &mut self.groups {
+            if group.contains(&en) {
+                Arc::make_mut(group).members.remove(&**en);
+            }
+        }
+    }
+}
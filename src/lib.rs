@@ -34,17 +34,39 @@ extern crate cereal;
 extern crate vec_map;
 
 pub use aspect::Aspect;
-pub use component::{Component, ComponentList};
+pub use component::{Change, Component, ComponentList, HasComponent, Search};
 pub use component::{EntityBuilder, EntityModifier};
-pub use entity::{Entity, IndexedEntity, EntityIter};
+pub use delta::{Delta, DiffComponents, DiffGroup};
+pub use dynamic::DynamicComponents;
+pub use entity::{Entity, EntityMap, IndexedEntity, EntityIter, MapEntities};
+pub use groups::{EntityGroup, EntityGroups};
+pub use names::{Names, Tag};
+pub use observer::{ObserverId, Observers, Trigger};
+pub use plugin::{CloneGroup, ComponentGroup, HasGroup, HasService};
+pub use resource::Resources;
 pub use system::{System, Process};
-pub use world::{ComponentManager, ServiceManager, SystemManager, DataHelper, World};
+pub use world::{CloneComponents, ComponentManager, ServiceManager, SystemManager, DataHelper, World};
+pub use world::{EntityBundle, Snapshot, SnapshotBuffer};
 
 use std::ops::Deref;
 
 pub mod aspect;
 pub mod component;
+pub mod delta;
+pub mod dynamic;
 pub mod entity;
+pub mod groups;
+pub mod names;
+pub mod observer;
+pub mod plugin;
+pub mod prefab;
+#[cfg(feature="serialisation")]
+pub mod replay;
+#[cfg(feature="serialisation")]
+pub mod replication;
+pub mod resource;
+#[cfg(feature="scripting")]
+pub mod scripting;
 pub mod system;
 pub mod world;
 
@@ -79,9 +101,9 @@ mod macros
     #[macro_export]
     macro_rules! process {
         {
-            $world:expr, $system:ident
+            $world:expr, $($system:ident).+
         } => {
-            $crate::Process::process(&mut $world.systems.$system, &mut $world.data)
+            $crate::Process::process(&mut $world.systems$(.$system)+, &mut $world.data)
         };
         {
             $world:expr, $system:ident . $function:ident ($($args:expr),*)
@@ -110,38 +132,104 @@ mod macros
                 {
 
                 }
+
+                fn __move_entity(&mut self, _: &mut $Name, _: &$crate::IndexedEntity<$Name>, _: &$crate::IndexedEntity<$Name>)
+                {
+
+                }
             }
-        };
-        {
-            #[builder($Builder:ident)]
-            $(#[$attr:meta])*
-            struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+
-            }
-        } => {
-            components!($(#[$attr])* struct $Name { $(#[$kind] $field_name : $field_ty),+ });
 
-            #[derive(Default)]
-            pub struct $Builder {
-                $(
-                    pub $field_name : Option<$field_ty>,
-                )+
+            impl $crate::DiffComponents for $Name
+            {
+                type Delta = ();
+
+                fn __diff(&self, _: &$Name, _: &[($crate::Entity, Option<usize>, usize)])
+                {
+
+                }
+
+                fn __apply(&mut self, _: &(), _: &mut dyn FnMut(&$crate::Entity) -> Option<usize>)
+                {
+
+                }
             }
 
-            impl $crate::EntityBuilder<$Name> for $Builder
+            impl $crate::CloneComponents for $Name
             {
-                fn build(self, e: $crate::BuildData<$Name>, c: &mut $Name)
+                fn __clone_entity(&mut self, _: &$crate::IndexedEntity<$Name>, _: &$crate::IndexedEntity<$Name>)
                 {
-                    $(
-                        self.$field_name.map(|cmpt| c.$field_name.add(&e, cmpt))
-                    );+;
+
+                }
+
+                fn __restore(&mut self, _: &$Name)
+                {
+
                 }
             }
         };
         {
-            $(#[$attr:meta])*
-            struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+
+            $(#[$($attr:tt)*])*
+            struct $Name:ident { $($fields:tt)* }
+        } => {
+            components!(@parse [] [] [] $(#[$($attr)*])* struct $Name { $($fields)* });
+        };
+
+        // Sort the ecs-specific attributes (options) from the ones to put on the struct.
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[builder($Builder:ident)]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)* (builder $Builder)] [$($dynamic)*] [$(#[$attr])*] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[modifier($Modifier:ident)]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)* (modifier $Modifier)] [$($dynamic)*] [$(#[$attr])*] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[dynamic($field:ident)]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)*] [$($dynamic)* $field] [$(#[$attr])*] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[replicated($($replicated:ident),*)]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)* (replicated $($replicated),*)] [$($dynamic)*] [$(#[$attr])*] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[has_component]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)* (has_component)] [$($dynamic)*] [$(#[$attr])*] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            #[$other:meta]
+            $($rest:tt)*
+        } => {
+            components!(@parse [$($option)*] [$($dynamic)*] [$(#[$attr])* #[$other]] $($rest)*);
+        };
+        {
+            @parse [$($option:tt)*] [$($dynamic:ident)*] [$(#[$attr:meta])*]
+            struct $Name:ident $fields:tt
+        } => {
+            components!(@struct [$(#[$attr])*] [$($dynamic)*] $Name $fields);
+            $(
+                components!(@option $Name $fields $option);
+            )*
+        };
+
+        {
+            @struct [$(#[$attr:meta])*] [$($dynamic:ident)*] $Name:ident {
+                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+ $(,)*
             }
         } => {
             $(#[$attr])*
@@ -149,6 +237,9 @@ mod macros
                 $(
                     pub $field_name : $crate::ComponentList<$Name, $field_ty>,
                 )+
+                $(
+                    pub $dynamic : $crate::DynamicComponents<$Name>,
+                )*
             }
 
             impl $crate::ComponentManager for $Name
@@ -159,6 +250,9 @@ mod macros
                         $(
                             $field_name : $crate::ComponentList::$kind()
                         ),+
+                        $(
+                            , $dynamic : $crate::DynamicComponents::new()
+                        )*
                     }
                 }
 
@@ -168,35 +262,193 @@ mod macros
                         self.$field_name.__clear(entity)
                     );+
                 }
+
+                fn __move_entity(&mut self, other: &mut $Name, from: &$crate::IndexedEntity<$Name>, to: &$crate::IndexedEntity<$Name>)
+                {
+                    $(
+                        self.$field_name.__move_entity(&mut other.$field_name, from, to);
+                    )+
+                }
+                $(
+                    fn __dynamic(&self) -> Option<&$crate::DynamicComponents<$Name>>
+                    {
+                        Some(&self.$dynamic)
+                    }
+
+                    fn __dynamic_mut(&mut self) -> Option<&mut $crate::DynamicComponents<$Name>>
+                    {
+                        Some(&mut self.$dynamic)
+                    }
+                )*
+            }
+
+            // The higher-ranked bounds stop the compiler rejecting this impl outright when a
+            // component type doesn't meet them. Instead, it simply doesn't apply.
+            impl $crate::DiffComponents for $Name
+                where $(for<'a> $field_ty: Clone + PartialEq),+
+            {
+                type Delta = ($($crate::delta::ListDelta<$field_ty>,)+);
+
+                fn __diff(&self, old: &$Name, entities: &[($crate::Entity, Option<usize>, usize)]) -> Self::Delta
+                {
+                    ($(self.$field_name.__diff(&old.$field_name, entities),)+)
+                }
+
+                fn __apply(&mut self, delta: &Self::Delta, index_of: &mut dyn FnMut(&$crate::Entity) -> Option<usize>)
+                {
+                    let ($(ref $field_name,)+) = *delta;
+                    $(
+                        self.$field_name.__apply_delta($field_name, index_of);
+                    )+
+                }
+            }
+
+            impl $crate::CloneComponents for $Name
+                where $(for<'a> $field_ty: Clone),+
+            {
+                fn __clone_entity(&mut self, from: &$crate::IndexedEntity<$Name>, to: &$crate::IndexedEntity<$Name>)
+                {
+                    $(
+                        self.$field_name.__clone_entity(from, to);
+                    )+
+                }
+
+                fn __restore(&mut self, snapshot: &$Name)
+                {
+                    $(
+                        self.$field_name.__restore(&snapshot.$field_name);
+                    )+
+                }
             }
         };
+
         {
-            #[builder($Builder:ident)]
-            $(#[$attr:meta])*
-            struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+,
-            }
+            @option $Name:ident {
+                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+ $(,)*
+            } (builder $Builder:ident)
         } => {
-            components!(
-                #[builder($Builder)]
-                $(#[$attr])*
-                struct $Name {
-                    $(#[$kind] $field_name : $field_ty),+
+            #[derive(Default)]
+            pub struct $Builder {
+                $(
+                    pub $field_name : Option<$field_ty>,
+                )+
+            }
+
+            impl $crate::EntityBuilder<$Name> for $Builder
+            {
+                fn build(self, e: $crate::BuildData<$Name>, c: &mut $Name)
+                {
+                    $(
+                        self.$field_name.map(|cmpt| c.$field_name.add(&e, cmpt))
+                    );+;
+                }
+            }
+
+            impl Clone for $Builder
+                where $(for<'a> $field_ty: Clone),+
+            {
+                fn clone(&self) -> $Builder
+                {
+                    $Builder {
+                        $(
+                            $field_name: self.$field_name.clone(),
+                        )+
+                    }
                 }
-            );
+            }
+
+            impl $crate::prefab::Template<$Name> for $Builder
+                where $(for<'a> $field_ty: Clone),+
+            {
+                fn overlay(&mut self, other: &$Builder)
+                {
+                    $(
+                        if other.$field_name.is_some() {
+                            self.$field_name = other.$field_name.clone();
+                        }
+                    )+
+                }
+            }
         };
         {
-            $(#[$attr:meta])*
-            struct $Name:ident {
-                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+,
+            @option $Name:ident {
+                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+ $(,)*
+            } (modifier $Modifier:ident)
+        } => {
+            #[derive(Default)]
+            pub struct $Modifier {
+                $(
+                    pub $field_name : $crate::Change<$field_ty>,
+                )+
             }
+
+            impl $crate::EntityModifier<$Name> for $Modifier
+            {
+                fn modify(self, e: $crate::ModifyData<$Name>, c: &mut $Name)
+                {
+                    $(
+                        c.$field_name.apply(&e, self.$field_name);
+                    )+
+                }
+            }
+        };
+        {
+            @option $Name:ident {
+                $(#[$kind:ident] $field_name:ident : $field_ty:ty),+ $(,)*
+            } (has_component)
         } => {
-            components!(
-                $(#[$attr])*
-                struct $Name {
-                    $(#[$kind] $field_name : $field_ty),+
+            $(
+                impl $crate::HasComponent<$field_ty> for $Name
+                {
+                    fn list(&self) -> &$crate::ComponentList<$Name, $field_ty>
+                    {
+                        &self.$field_name
+                    }
+
+                    fn list_mut(&mut self) -> &mut $crate::ComponentList<$Name, $field_ty>
+                    {
+                        &mut self.$field_name
+                    }
+                }
+            )+
+        };
+        {
+            @option $Name:ident $fields:tt (replicated $($replicated:ident),*)
+        } => {
+            impl $crate::replication::Replicated for $Name
+            {
+                fn __encode(&self, index: usize) -> $crate::replication::CerealResult<Vec<Option<Vec<u8>>>>
+                {
+                    Ok(vec![$(self.$replicated.__encode(index)?),*])
+                }
+
+                fn __decode(&mut self, entity: &$crate::IndexedEntity<$Name>, changes: &[$crate::replication::FieldChange]) -> $crate::replication::CerealResult<()>
+                {
+                    let mut _changes = changes.iter();
+                    $(
+                        if let Some(change) = _changes.next() {
+                            self.$replicated.__decode(entity, change)?;
+                        }
+                    )*
+                    Ok(())
                 }
-            );
+
+                fn __track_changes(&self) -> Vec<u64>
+                {
+                    vec![$(self.$replicated.__track_changes()),*]
+                }
+
+                fn __changed_since(&self, index: usize, versions: &[u64]) -> bool
+                {
+                    let mut _versions = versions.iter();
+                    $(
+                        if _versions.next().map_or(true, |&v| self.$replicated.__changed_since(index, v)) {
+                            return true;
+                        }
+                    )*
+                    false
+                }
+            }
         };
     }
 
@@ -249,11 +501,40 @@ mod macros
                     $($p_field_name:ident : $p_field_ty:ty = $p_field_init:expr,)*
                 }
             }
+        } => {
+            systems! {
+                $(#[$attr])*
+                struct $Name<$components, $services> {
+                    active: {
+                        $($field_name : $field_ty = $field_init,)*
+                    },
+                    passive: {
+                        $($p_field_name : $p_field_ty = $p_field_init,)*
+                    },
+                    plugins: {
+                    }
+                }
+            }
+        };
+        {
+            $(#[$attr:meta])*
+            struct $Name:ident<$components:ty, $services:ty> {
+                active: {
+                    $($field_name:ident : $field_ty:ty = $field_init:expr,)*
+                },
+                passive: {
+                    $($p_field_name:ident : $p_field_ty:ty = $p_field_init:expr,)*
+                },
+                plugins: {
+                    $($plugin_name:ident : $plugin_ty:ty,)*
+                }
+            }
         } => {
             $(#[$attr])*
             pub struct $Name {
                 $(pub $field_name : $field_ty,)*
                 $(pub $p_field_name : $p_field_ty,)*
+                $(pub $plugin_name : $plugin_ty,)*
             }
 
             impl $crate::SystemManager for $Name
@@ -269,6 +550,9 @@ mod macros
                         $(
                             $p_field_name : $p_field_init,
                         )*
+                        $(
+                            $plugin_name : $crate::SystemManager::__new(),
+                        )*
                     }
                 }
 
@@ -280,6 +564,9 @@ mod macros
                     $(
                         $crate::System::activated(&mut self.$p_field_name, &en, co, se);
                     )*
+                    $(
+                        $crate::SystemManager::__activated(&mut self.$plugin_name, en, co, se);
+                    )*
                 }
 
                 fn __reactivated(&mut self, en: $crate::EntityData<$components>, co: &$components, se: &mut $services)
@@ -290,6 +577,9 @@ mod macros
                     $(
                         $crate::System::reactivated(&mut self.$p_field_name, &en, co, se);
                     )*
+                    $(
+                        $crate::SystemManager::__reactivated(&mut self.$plugin_name, en, co, se);
+                    )*
                 }
 
                 fn __deactivated(&mut self, en: $crate::EntityData<$components>, co: &$components, se: &mut $services)
@@ -300,6 +590,9 @@ mod macros
                     $(
                         $crate::System::deactivated(&mut self.$p_field_name, &en, co, se);
                     )*
+                    $(
+                        $crate::SystemManager::__deactivated(&mut self.$plugin_name, en, co, se);
+                    )*
                 }
 
                 fn __update(&mut self, _co: &mut $crate::DataHelper<$components, $services>)
@@ -307,6 +600,9 @@ mod macros
                     $(
                         $crate::Process::process(&mut self.$field_name, _co);
                     )*
+                    $(
+                        $crate::SystemManager::__update(&mut self.$plugin_name, _co);
+                    )*
                 }
             }
         };
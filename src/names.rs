@@ -0,0 +1,215 @@
+
+//! Looking up entities by name or tag.
+//!
+//! Each world keeps a `Names` index alongside its entities. An entity can have one name, which
+//! no other entity shares, and any number of tags:
+//!
+//! ```ignore
+//! world.set_name(&player, "player");
+//! world.add_tag(&goblin, "enemy");
+//!
+//! let player = world.names().entity("player").unwrap();
+//! if let Some(enemy) = world.names().tag("enemy") {
+//!     for entity in world.names().tagged(enemy) { /* ... */ }
+//! }
+//! ```
+//!
+//! Tag strings are interned, so a `Tag` can be looked up once and then used to check or iterate
+//! entities without hashing the string again. Entities are taken out of the index when they're
+//! removed from the world.
+
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(feature="serialisation")] use cereal::{CerealData, CerealError, CerealResult};
+#[cfg(feature="serialisation")] use std::io::{Read, Write};
+
+use Entity;
+
+/// An interned tag, from `Names::tag`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct Tag(usize);
+
+/// The names and tags of a world's entities.
+#[derive(Clone, Default)]
+pub struct Names
+{
+    entities: HashMap<String, Entity>,
+    names: HashMap<Entity, String>,
+    tag_ids: HashMap<String, Tag>,
+    tag_names: Vec<String>,
+    tagged: Vec<BTreeSet<Entity>>,
+    tags: HashMap<Entity, BTreeSet<Tag>>,
+}
+
+impl Names
+{
+    pub fn new() -> Names
+    {
+        Names::default()
+    }
+
+    /// The entity with this name.
+    pub fn entity(&self, name: &str) -> Option<Entity>
+    {
+        self.entities.get(name).cloned()
+    }
+
+    pub fn name(&self, entity: &Entity) -> Option<&str>
+    {
+        self.names.get(entity).map(|name| &name[..])
+    }
+
+    /// The tag for this string, if it's ever been given to an entity.
+    pub fn tag(&self, name: &str) -> Option<Tag>
+    {
+        self.tag_ids.get(name).cloned()
+    }
+
+    pub fn tag_name(&self, tag: Tag) -> &str
+    {
+        &self.tag_names[tag.0]
+    }
+
+    /// The entities with a tag, in the order they were created.
+    pub fn tagged(&self, tag: Tag) -> impl Iterator<Item=Entity> + '_
+    {
+        self.tagged[tag.0].iter().cloned()
+    }
+
+    pub fn has_tag(&self, entity: &Entity, tag: Tag) -> bool
+    {
+        self.tagged[tag.0].contains(entity)
+    }
+
+    pub fn tags_of(&self, entity: &Entity) -> impl Iterator<Item=Tag> + '_
+    {
+        self.tags.get(entity).into_iter().flat_map(|tags| tags.iter().cloned())
+    }
+
+    /// Names an entity, taking the name from any entity that already had it.
+    ///
+    /// Returns the entity that lost the name, if any.
+    pub fn set_name(&mut self, entity: Entity, name: &str) -> Option<Entity>
+    {
+        self.remove_name(&entity);
+        let previous = self.entities.insert(name.to_string(), entity);
+        if let Some(ref previous) = previous {
+            self.names.remove(previous);
+        }
+        self.names.insert(entity, name.to_string());
+        previous
+    }
+
+    /// Removes an entity's name, returning it.
+    pub fn remove_name(&mut self, entity: &Entity) -> Option<String>
+    {
+        let name = self.names.remove(entity);
+        if let Some(ref name) = name {
+            self.entities.remove(name);
+        }
+        name
+    }
+
+    /// Tags an entity, returning the interned tag.
+    pub fn add_tag(&mut self, entity: Entity, name: &str) -> Tag
+    {
+        let tag = self.intern(name);
+        self.tagged[tag.0].insert(entity);
+        self.tags.entry(entity).or_default().insert(tag);
+        tag
+    }
+
+    /// Removes a tag from an entity, returning whether it had it.
+    pub fn remove_tag(&mut self, entity: &Entity, tag: Tag) -> bool
+    {
+        if let Some(tags) = self.tags.get_mut(entity) {
+            tags.remove(&tag);
+            if tags.is_empty() {
+                self.tags.remove(entity);
+            }
+        }
+        self.tagged[tag.0].remove(entity)
+    }
+
+    /// Removes the entity's name and tags.
+    pub fn remove(&mut self, entity: &Entity)
+    {
+        self.remove_name(entity);
+        for tag in self.tags.remove(entity).into_iter().flat_map(|tags| tags.into_iter()) {
+            self.tagged[tag.0].remove(entity);
+        }
+    }
+
+    /// Gives `to` the name and tags that `from` had in `other`, taking them out of `other`.
+    ///
+    /// Used when entities move between worlds, where tags are interned separately. As with
+    /// `set_name`, the name is taken from any entity here that already had it.
+    pub fn move_entity(&mut self, other: &mut Names, from: &Entity, to: Entity)
+    {
+        if let Some(name) = other.remove_name(from) {
+            self.set_name(to, &name);
+        }
+        for tag in other.tags.remove(from).into_iter().flat_map(|tags| tags.into_iter()) {
+            other.tagged[tag.0].remove(from);
+            self.add_tag(to, &other.tag_names[tag.0]);
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> Tag
+    {
+        if let Some(&tag) = self.tag_ids.get(name) {
+            return tag;
+        }
+        let tag = Tag(self.tag_names.len());
+        self.tag_ids.insert(name.to_string(), tag);
+        self.tag_names.push(name.to_string());
+        self.tagged.push(BTreeSet::new());
+        tag
+    }
+}
+
+#[cfg(feature="serialisation")]
+unsafe impl CerealData for Names {
+    fn write(&self, w: &mut Write) -> CerealResult<()> {
+        try!(self.tag_names.write(w));
+        // Sorted so that the same names are always written the same way.
+        let mut names: Vec<_> = self.names.iter().collect();
+        names.sort_by_key(|&(entity, _)| *entity);
+        try!((names.len() as u64).write(w));
+        for (entity, name) in names {
+            try!(entity.write(w));
+            try!(name.write(w));
+        }
+        let mut tags: Vec<_> = self.tags.iter().collect();
+        tags.sort_by_key(|&(entity, _)| *entity);
+        try!((tags.len() as u64).write(w));
+        for (entity, tags) in tags {
+            try!(entity.write(w));
+            try!(tags.iter().map(|tag| tag.0 as u64).collect::<Vec<_>>().write(w));
+        }
+        Ok(())
+    }
+
+    fn read(r: &mut Read) -> CerealResult<Names> {
+        let mut names = Names::new();
+        let tag_names: Vec<String> = try!(CerealData::read(r));
+        for name in &tag_names {
+            names.intern(name);
+        }
+        for _ in 0..try!(u64::read(r)) {
+            let entity = try!(CerealData::read(r));
+            let name: String = try!(CerealData::read(r));
+            names.set_name(entity, &name);
+        }
+        for _ in 0..try!(u64::read(r)) {
+            let entity = try!(CerealData::read(r));
+            let tags: Vec<u64> = try!(CerealData::read(r));
+            for tag in tags {
+                let name = try!(tag_names.get(tag as usize)
+                    .ok_or_else(|| CerealError::Msg(format!("Unknown tag {}", tag))));
+                names.add_tag(entity, name);
+            }
+        }
+        Ok(names)
+    }
+}
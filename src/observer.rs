@@ -0,0 +1,186 @@
+
+//! Callbacks that react to entities being created, changed and removed.
+//!
+//! Observers hear about the same events as systems, when the queue is flushed or an entity is
+//! modified, but can be added and removed at any time. Each one has an `Aspect`, and is only
+//! told about entities that match it:
+//!
+//! ```ignore
+//! world.add_observer(Trigger::Enter, aspect!(<MyComponents> all: [burning]), |e, c, services| {
+//!     services.audio.play("ignite");
+//! });
+//! ```
+//!
+//! `Trigger::Enter` and `Trigger::Exit` fire when an entity starts or stops matching, so an
+//! aspect naming a single component reacts to that component being added or removed.
+
+use std::collections::HashSet;
+
+use Aspect;
+use {Entity, EntityData};
+use {ComponentManager, ServiceManager};
+use world::Listener;
+
+/// The events an observer can react to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trigger
+{
+    /// A matching entity is built.
+    Create,
+    /// A matching entity is modified, and still matches afterwards.
+    Modify,
+    /// A matching entity is removed.
+    Remove,
+    /// An entity starts matching, either by being built or by being modified.
+    Enter,
+    /// An entity stops matching, either by being modified or by being removed.
+    Exit,
+}
+
+/// Identifies an observer, so that it can be removed again.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+type Callback<C, M> = Box<dyn FnMut(EntityData<C>, &C, &mut M) + Send>;
+
+struct Observer<C: ComponentManager, M: ServiceManager>
+{
+    id: ObserverId,
+    trigger: Trigger,
+    aspect: Aspect<C>,
+    callback: Callback<C, M>,
+    matching: HashSet<Entity>,
+}
+
+impl<C: ComponentManager, M: ServiceManager> Observer<C, M>
+{
+    fn fire(&mut self, trigger: Trigger, entity: EntityData<C>, components: &C, services: &mut M)
+    {
+        if self.trigger == trigger {
+            (self.callback)(entity, components, services);
+        }
+    }
+}
+
+/// The observers of a world, run after its systems in the order they were added.
+///
+/// Each world has one in `World::observers`. Observers should be added with
+/// `World::add_observer`, so that entities which already match aren't mistaken for new ones.
+pub struct Observers<C: ComponentManager, M: ServiceManager>
+{
+    observers: Vec<Observer<C, M>>,
+    next_id: u64,
+}
+
+impl<C: ComponentManager, M: ServiceManager> Observers<C, M>
+{
+    pub fn new() -> Observers<C, M>
+    {
+        Observers {
+            observers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.observers.is_empty()
+    }
+
+    /// Adds an observer that treats every entity as not matching until it next hears about it.
+    pub fn add<F>(&mut self, trigger: Trigger, aspect: Aspect<C>, callback: F) -> ObserverId
+        where F: FnMut(EntityData<C>, &C, &mut M) + Send + 'static
+    {
+        let id = ObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push(Observer {
+            id,
+            trigger,
+            aspect,
+            callback: Box::new(callback),
+            matching: HashSet::new(),
+        });
+        id
+    }
+
+    /// Removes an observer, returning whether it existed.
+    pub fn remove(&mut self, id: ObserverId) -> bool
+    {
+        let len = self.observers.len();
+        self.observers.retain(|observer| observer.id != id);
+        self.observers.len() != len
+    }
+
+    pub fn contains(&self, id: ObserverId) -> bool
+    {
+        self.observers.iter().any(|observer| observer.id == id)
+    }
+
+    /// Records that an entity already matches the observer, without running it.
+    #[doc(hidden)]
+    pub fn __track(&mut self, id: ObserverId, entity: &EntityData<C>, components: &C)
+    {
+        if let Some(observer) = self.observers.iter_mut().find(|observer| observer.id == id) {
+            if observer.aspect.check(entity, components) {
+                observer.matching.insert(***entity);
+            }
+        }
+    }
+}
+
+impl<C: ComponentManager, M: ServiceManager> Default for Observers<C, M>
+{
+    fn default() -> Observers<C, M>
+    {
+        Observers::new()
+    }
+}
+
+impl<C: ComponentManager, M: ServiceManager> Listener<C, M> for Observers<C, M>
+{
+    fn __activate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for observer in &mut self.observers {
+            if observer.aspect.check(&en, co) {
+                observer.matching.insert(**en);
+                observer.fire(Trigger::Create, en, co, se);
+                observer.fire(Trigger::Enter, en, co, se);
+            }
+        }
+    }
+
+    fn __reactivate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for observer in &mut self.observers {
+            let was = observer.matching.contains(&**en);
+            let now = observer.aspect.check(&en, co);
+            match (was, now) {
+                (false, true) => {
+                    observer.matching.insert(**en);
+                    observer.fire(Trigger::Enter, en, co, se);
+                },
+                (true, false) => {
+                    observer.matching.remove(&**en);
+                    observer.fire(Trigger::Exit, en, co, se);
+                },
+                (true, true) => observer.fire(Trigger::Modify, en, co, se),
+                (false, false) => (),
+            }
+        }
+    }
+
+    fn __deactivate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for observer in &mut self.observers {
+            if observer.matching.remove(&**en) {
+                observer.fire(Trigger::Remove, en, co, se);
+                observer.fire(Trigger::Exit, en, co, se);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+
+//! Splitting a world's components, services and systems between plugins.
+//!
+//! A plugin declares its own group of component lists, its services and its systems, all
+//! generic over the component manager (and service manager) they end up in. These use the
+//! derives from `ecs_derive`:
+//!
+//! ```ignore
+//! #[derive(ComponentGroup)]
+//! pub struct PhysicsComponents<C: ComponentManager> {
+//!     #[storage(hot)] pub position: ComponentList<C, Position>,
+//!     #[storage(hot)] pub velocity: ComponentList<C, Velocity>,
+//! }
+//!
+//! #[derive(Default)]
+//! pub struct PhysicsServices {
+//!     pub gravity: f32,
+//! }
+//!
+//! #[derive(SystemManager)]
+//! #[components(C)]
+//! #[services(M)]
+//! pub struct PhysicsSystems<C, M>
+//!     where C: HasGroup<PhysicsComponents<C>>, M: ServiceManager + HasService<PhysicsServices>
+//! {
+//!     #[active(EntitySystem::new(Motion::new(),
+//!         Aspect::all().in_group(|g: &PhysicsComponents<C>, e| g.position.has(e) && g.velocity.has(e))))]
+//!     pub motion: EntitySystem<Motion<C, M>>,
+//! }
+//! ```
+//!
+//! The game then puts each plugin's parts into its own managers with `#[plugin]` fields:
+//!
+//! ```ignore
+//! #[derive(ComponentManager)]
+//! pub struct GameComponents {
+//!     #[plugin] pub physics: PhysicsComponents<Self>,
+//!     #[storage(cold)] pub name: ComponentList<Self, String>,
+//! }
+//!
+//! #[derive(ServiceManager, Default)]
+//! pub struct GameServices {
+//!     #[plugin] pub physics: PhysicsServices,
+//! }
+//!
+//! #[derive(SystemManager)]
+//! #[components(GameComponents)]
+//! #[services(GameServices)]
+//! pub struct GameSystems {
+//!     #[plugin] pub physics: PhysicsSystems<GameComponents, GameServices>,
+//! }
+//! ```
+//!
+//! Each plugin's systems are reached through the field holding them, as in
+//! `world.systems.physics.motion` or `process!(world, physics.motion)`.
+
+use {ComponentManager, IndexedEntity, ServiceManager};
+
+/// A set of component lists belonging to the component manager `C`.
+pub trait ComponentGroup<C: ComponentManager>: 'static+Sized
+{
+    #[doc(hidden)]
+    fn __new() -> Self;
+    #[doc(hidden)]
+    fn __remove_all(&mut self, entity: &IndexedEntity<C>);
+    #[doc(hidden)]
+    fn __move_entity(&mut self, other: &mut Self, from: &IndexedEntity<C>, to: &IndexedEntity<C>);
+}
+
+/// Component groups whose components can be copied between entities, like `CloneComponents`.
+pub trait CloneGroup<C: ComponentManager>: ComponentGroup<C>
+{
+    #[doc(hidden)]
+    fn __clone_entity(&mut self, from: &IndexedEntity<C>, to: &IndexedEntity<C>);
+    #[doc(hidden)]
+    fn __restore(&mut self, snapshot: &Self);
+}
+
+/// Component managers holding the component group `G`.
+pub trait HasGroup<G>: ComponentManager
+{
+    fn group(&self) -> &G;
+    fn group_mut(&mut self) -> &mut G;
+}
+
+/// Service managers holding a `T`.
+pub trait HasService<T>: ServiceManager
+{
+    fn service(&self) -> &T;
+    fn service_mut(&mut self) -> &mut T;
+}
@@ -0,0 +1,229 @@
+
+//! Named templates for spawning entities.
+//!
+//! A template is the builder generated by `#[builder(Name)]`, with an `Option` for each
+//! component. Templates are plain values, so they can be filled in from any data file. A
+//! `Prefab` holds one template per entity it spawns (most have just one), and may name a parent
+//! whose templates it overrides:
+//!
+//! ```ignore
+//! let mut prefabs = PrefabRegistry::new();
+//! prefabs.insert("goblin", Prefab::new(EntityInit { health: Some(Health(10)), ..Default::default() }));
+//! prefabs.insert("goblin_chief", Prefab::inherit("goblin", EntityInit { health: Some(Health(30)), ..Default::default() }));
+//!
+//! let goblin = data.create_entity(prefabs.instance("goblin", EntityInit { position: Some(pos), ..Default::default() })?);
+//! ```
+//!
+//! Prefabs with several parts can link them together once every part has been created, such as
+//! a rider pointing at its mount:
+//!
+//! ```ignore
+//! let mut cavalry = Prefab::new(rider);
+//! cavalry.add_part(mount);
+//! cavalry.link(0, |e, c: &mut MyComponents, parts| { c.mount.set(&e, Mount(parts[1])); });
+//! let entities = prefabs.spawn("cavalry", Default::default(), &mut data)?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use {ComponentManager, DataHelper, Entity, EntityBuilder, EntityData, ServiceManager};
+
+/// Builders that can be copied and layered on top of one another.
+///
+/// Implemented by the builders generated with `#[builder(Name)]` whenever every component type
+/// is `Clone`.
+pub trait Template<C: ComponentManager>: EntityBuilder<C> + Clone + Default
+{
+    /// Replaces each component that `other` sets.
+    fn overlay(&mut self, other: &Self);
+}
+
+type Link<C> = Arc<dyn Fn(EntityData<C>, &mut C, &[Entity]) + Send + Sync>;
+
+/// The templates for one or more entities spawned together.
+pub struct Prefab<C: ComponentManager, T: Template<C>>
+{
+    parent: Option<String>,
+    parts: Vec<T>,
+    links: Vec<(usize, Link<C>)>,
+}
+
+impl<C: ComponentManager, T: Template<C>> Prefab<C, T>
+{
+    pub fn new(template: T) -> Prefab<C, T>
+    {
+        Prefab {
+            parent: None,
+            parts: vec![template],
+            links: Vec::new(),
+        }
+    }
+
+    /// A prefab based on the one registered as `parent`, with the given components overridden.
+    pub fn inherit(parent: &str, overrides: T) -> Prefab<C, T>
+    {
+        Prefab {
+            parent: Some(parent.to_string()),
+            parts: vec![overrides],
+            links: Vec::new(),
+        }
+    }
+
+    /// Adds another entity to be spawned, returning its part number.
+    ///
+    /// When inheriting, each part overrides the parent's part with the same number.
+    pub fn add_part(&mut self, template: T) -> usize
+    {
+        self.parts.push(template);
+        self.parts.len() - 1
+    }
+
+    /// Runs `link` on the entity spawned for `part`, once every part has been created.
+    ///
+    /// It's given the entities spawned for each part, so that components can refer to them.
+    /// Links must be `Send + Sync` so that the registry can be kept in the world's resources.
+    pub fn link<F>(&mut self, part: usize, link: F)
+        where F: Fn(EntityData<C>, &mut C, &[Entity]) + Send + Sync + 'static
+    {
+        self.links.push((part, Arc::new(link)));
+    }
+
+    pub fn parent(&self) -> Option<&str>
+    {
+        self.parent.as_ref().map(|parent| &parent[..])
+    }
+
+    pub fn parts(&self) -> &[T]
+    {
+        &self.parts
+    }
+}
+
+impl<C: ComponentManager, T: Template<C>> Clone for Prefab<C, T>
+{
+    fn clone(&self) -> Prefab<C, T>
+    {
+        Prefab {
+            parent: self.parent.clone(),
+            parts: self.parts.clone(),
+            links: self.links.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrefabError
+{
+    /// No prefab with this name has been registered.
+    Unknown(String),
+    /// The prefab ends up inheriting from itself.
+    Cycle(String),
+}
+
+impl fmt::Display for PrefabError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self {
+            PrefabError::Unknown(ref name) => write!(f, "No prefab called `{}`", name),
+            PrefabError::Cycle(ref name) => write!(f, "Prefab `{}` inherits from itself", name),
+        }
+    }
+}
+
+/// Prefabs by name.
+pub struct PrefabRegistry<C: ComponentManager, T: Template<C>>
+{
+    prefabs: HashMap<String, Prefab<C, T>>,
+}
+
+impl<C: ComponentManager, T: Template<C>> PrefabRegistry<C, T>
+{
+    pub fn new() -> PrefabRegistry<C, T>
+    {
+        PrefabRegistry {
+            prefabs: HashMap::new(),
+        }
+    }
+
+    /// Registers a prefab, returning the one it replaced.
+    ///
+    /// Parents are looked up when the prefab is used, so they can be registered in any order.
+    pub fn insert(&mut self, name: &str, prefab: Prefab<C, T>) -> Option<Prefab<C, T>>
+    {
+        self.prefabs.insert(name.to_string(), prefab)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab<C, T>>
+    {
+        self.prefabs.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Prefab<C, T>>
+    {
+        self.prefabs.remove(name)
+    }
+
+    /// The prefab with everything it inherits folded in.
+    pub fn resolve(&self, name: &str) -> Result<Prefab<C, T>, PrefabError>
+    {
+        let mut chain = Vec::new();
+        let mut next = Some(name);
+        while let Some(name) = next {
+            let prefab = self.prefabs.get(name).ok_or_else(|| PrefabError::Unknown(name.to_string()))?;
+            if chain.iter().any(|&(other, _)| other == name) {
+                return Err(PrefabError::Cycle(name.to_string()));
+            }
+            chain.push((name, prefab));
+            next = prefab.parent();
+        }
+
+        let mut resolved: Prefab<C, T> = Prefab { parent: None, parts: Vec::new(), links: Vec::new() };
+        for &(_, prefab) in chain.iter().rev() {
+            for (i, part) in prefab.parts.iter().enumerate() {
+                match resolved.parts.get_mut(i) {
+                    Some(base) => base.overlay(part),
+                    None => resolved.parts.push(part.clone()),
+                }
+            }
+            resolved.links.extend(prefab.links.iter().cloned());
+        }
+        Ok(resolved)
+    }
+
+    /// The first part of a prefab with `overrides` on top, to pass to `create_entity`.
+    ///
+    /// Only the first part is used, and links are ignored; `spawn` creates every part.
+    pub fn instance(&self, name: &str, overrides: T) -> Result<T, PrefabError>
+    {
+        let mut template = self.resolve(name)?.parts.swap_remove(0);
+        template.overlay(&overrides);
+        Ok(template)
+    }
+
+    /// Creates an entity for every part of a prefab, applying `overrides` to the first, and then
+    /// runs its links. Returns the entities in part order.
+    pub fn spawn<M: ServiceManager>(&self, name: &str, overrides: T, data: &mut DataHelper<C, M>)
+        -> Result<Vec<Entity>, PrefabError>
+    {
+        let mut prefab = self.resolve(name)?;
+        prefab.parts[0].overlay(&overrides);
+        let entities: Vec<Entity> = prefab.parts.into_iter().map(|part| data.create_entity(part)).collect();
+        for (part, link) in prefab.links {
+            if let Some(entity) = entities.get(part) {
+                data.with_entity_data(entity, |e, c| link(e, c, &entities));
+            }
+        }
+        Ok(entities)
+    }
+}
+
+impl<C: ComponentManager, T: Template<C>> Default for PrefabRegistry<C, T>
+{
+    fn default() -> PrefabRegistry<C, T>
+    {
+        PrefabRegistry::new()
+    }
+}
@@ -0,0 +1,257 @@
+
+//! Recording the inputs to a world and replaying them deterministically.
+//!
+//! Every external change to the world is expressed as a `Command` and applied through a
+//! `Recorder`, which logs the commands given before each `update` along with a hash of the
+//! world's state afterwards. A `Replayer` starts from the state saved when recording began,
+//! applies the same commands frame by frame, and checks that the hashes still match.
+//!
+//! Recording and replaying both switch the world to `set_deterministic`, so entities are
+//! iterated in order of their id. New ids are handed out in sequence, so the same commands
+//! applied to the same state produce the same result. Systems start fresh when replaying, so
+//! any state they hold outside of the world won't be reproduced.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use cereal::{CerealData, CerealError, CerealResult};
+
+use {ComponentManager, DataHelper, ServiceManager, SystemManager, World};
+
+/// An external change to a world, such as creating an entity or modifying a service.
+pub trait Command<S: SystemManager>
+{
+    fn apply(&self, world: &mut World<S>);
+}
+
+/// The commands applied before a single update, and the hash of the state that followed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame<T>
+{
+    pub commands: Vec<T>,
+    pub hash: u64,
+}
+
+unsafe impl<T: CerealData> CerealData for Frame<T> {
+    fn write(&self, w: &mut Write) -> CerealResult<()> {
+        try!(self.commands.write(w));
+        self.hash.write(w)
+    }
+
+    fn read(r: &mut Read) -> CerealResult<Frame<T>> {
+        Ok(Frame {
+            commands: try!(CerealData::read(r)),
+            hash: try!(CerealData::read(r)),
+        })
+    }
+}
+
+/// Everything needed to replay a session: the starting state and every frame after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording<T>
+{
+    /// The world as written by `World::save` when recording started.
+    pub initial: Vec<u8>,
+    pub frames: Vec<Frame<T>>,
+}
+
+unsafe impl<T: CerealData> CerealData for Recording<T> {
+    fn write(&self, w: &mut Write) -> CerealResult<()> {
+        try!(self.initial.write(w));
+        self.frames.write(w)
+    }
+
+    fn read(r: &mut Read) -> CerealResult<Recording<T>> {
+        Ok(Recording {
+            initial: try!(CerealData::read(r)),
+            frames: try!(CerealData::read(r)),
+        })
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is fixed, so a recording made with
+/// one version of Rust can be checked with another.
+struct HashWriter(u64);
+
+impl HashWriter
+{
+    fn new() -> HashWriter
+    {
+        HashWriter(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Write for HashWriter
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        for &byte in buf {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+/// Hashes everything that would be written by `World::save`.
+pub fn state_hash<C, M>(data: &DataHelper<C, M>) -> CerealResult<u64>
+    where C: ComponentManager, M: ServiceManager, DataHelper<C, M>: CerealData
+{
+    let mut hasher = HashWriter::new();
+    try!(data.write(&mut hasher));
+    Ok(hasher.0)
+}
+
+/// Applies commands to a world and logs them.
+pub struct Recorder<T>
+{
+    recording: Recording<T>,
+    pending: Vec<T>,
+}
+
+impl<T> Recorder<T>
+{
+    /// Starts recording from the world's current state, switching it to iterate entities in
+    /// order of id.
+    pub fn start<S>(world: &mut World<S>) -> CerealResult<Recorder<T>>
+        where S: SystemManager, DataHelper<S::Components, S::Services>: CerealData
+    {
+        world.set_deterministic(true);
+        let mut initial = Vec::new();
+        try!(world.save(&mut initial));
+        Ok(Recorder {
+            recording: Recording {
+                initial,
+                frames: Vec::new(),
+            },
+            pending: Vec::new(),
+        })
+    }
+
+    /// Applies a command to the world, logging it for the current frame.
+    pub fn apply<S>(&mut self, world: &mut World<S>, command: T)
+        where S: SystemManager, T: Command<S>
+    {
+        command.apply(world);
+        self.pending.push(command);
+    }
+
+    /// Updates the world, ending the current frame.
+    pub fn update<S>(&mut self, world: &mut World<S>) -> CerealResult<()>
+        where S: SystemManager, DataHelper<S::Components, S::Services>: CerealData
+    {
+        world.update();
+        let hash = try!(state_hash(&world.data));
+        self.recording.frames.push(Frame {
+            commands: ::std::mem::take(&mut self.pending),
+            hash,
+        });
+        Ok(())
+    }
+
+    /// Returns what has been recorded so far.
+    pub fn recording(&self) -> &Recording<T>
+    {
+        &self.recording
+    }
+
+    /// Stops recording. Commands applied since the last update are discarded.
+    pub fn finish(self) -> Recording<T>
+    {
+        self.recording
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError
+{
+    Cereal(CerealError),
+    /// The state after a frame didn't match the recorded hash.
+    Desync { frame: usize, expected: u64, found: u64 },
+}
+
+impl fmt::Display for ReplayError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self {
+            ReplayError::Cereal(ref err) => write!(f, "Could not read or hash the world: {:?}", err),
+            ReplayError::Desync { frame, expected, found } =>
+                write!(f, "Desync at frame {}: expected state hash {:x}, found {:x}", frame, expected, found),
+        }
+    }
+}
+
+impl From<CerealError> for ReplayError
+{
+    fn from(err: CerealError) -> ReplayError
+    {
+        ReplayError::Cereal(err)
+    }
+}
+
+/// Reconstructs a recorded session frame by frame.
+pub struct Replayer<'a, S: SystemManager, T: 'a>
+{
+    world: World<S>,
+    frames: &'a [Frame<T>],
+    frame: usize,
+}
+
+impl<'a, S: SystemManager, T: Command<S>> Replayer<'a, S, T>
+    where DataHelper<S::Components, S::Services>: CerealData
+{
+    /// Loads the starting state of a recording.
+    pub fn new(recording: &'a Recording<T>) -> CerealResult<Replayer<'a, S, T>>
+    {
+        let mut world: World<S> = try!(World::load(&mut &recording.initial[..]));
+        world.set_deterministic(true);
+        Ok(Replayer {
+            world,
+            frames: &recording.frames,
+            frame: 0,
+        })
+    }
+
+    /// Replays the next frame, returning false if there were none left.
+    pub fn step(&mut self) -> Result<bool, ReplayError>
+    {
+        let frame = match self.frames.get(self.frame) {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
+        for command in &frame.commands {
+            command.apply(&mut self.world);
+        }
+        self.world.update();
+        let found = try!(state_hash(&self.world.data));
+        if found != frame.hash {
+            return Err(ReplayError::Desync { frame: self.frame, expected: frame.hash, found });
+        }
+        self.frame += 1;
+        Ok(true)
+    }
+
+    /// Replays every remaining frame, returning the final world.
+    pub fn run(mut self) -> Result<World<S>, ReplayError>
+    {
+        while try!(self.step()) {}
+        Ok(self.world)
+    }
+
+    /// The index of the next frame to be replayed.
+    pub fn frame(&self) -> usize
+    {
+        self.frame
+    }
+
+    pub fn world(&self) -> &World<S>
+    {
+        &self.world
+    }
+}
@@ -0,0 +1,356 @@
+
+//! Mirroring a world to clients.
+//!
+//! Only the component lists named in a `#[replicated(...)]` attribute on the `components!`
+//! declaration are sent, and their component types must implement `CerealData`:
+//!
+//! ```ignore
+//! components! {
+//!     #[replicated(position, sprite)]
+//!     struct MyComponents {
+//!         #[hot] position: Position,
+//!         #[hot] velocity: Velocity,
+//!         #[cold] sprite: Sprite,
+//!     }
+//! }
+//! ```
+//!
+//! A `ReplicationServer` remembers what it last sent to each client, so each call to
+//! `replicate` only sends what changed for that client. A `ReplicationClient` applies the
+//! changes to its own world, creating local entities and keeping track of which server
+//! entity each one mirrors.
+//!
+//! Packets are assumed to arrive reliably and in order.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Entry;
+use std::rc::Rc;
+
+pub use cereal::CerealResult;
+use cereal::{CerealData, CerealError};
+
+use BuildData;
+use {ComponentManager, DataHelper, ServiceManager, SystemManager, World};
+use {Entity, IndexedEntity};
+
+/// Sends and receives packets for one connection.
+pub trait Transport
+{
+    /// Sends a packet to the other end of the connection.
+    fn send(&mut self, packet: Vec<u8>);
+    /// Returns the next packet received, if there is one.
+    fn receive(&mut self) -> Option<Vec<u8>>;
+}
+
+/// An in-process `Transport`, mostly useful for testing.
+pub struct Loopback
+{
+    outgoing: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    incoming: Rc<RefCell<VecDeque<Vec<u8>>>>,
+}
+
+impl Loopback
+{
+    /// Creates both ends of a connection.
+    pub fn pair() -> (Loopback, Loopback)
+    {
+        let a = Rc::new(RefCell::new(VecDeque::new()));
+        let b = Rc::new(RefCell::new(VecDeque::new()));
+        (
+            Loopback { outgoing: a.clone(), incoming: b.clone() },
+            Loopback { outgoing: b, incoming: a },
+        )
+    }
+}
+
+impl Transport for Loopback
+{
+    fn send(&mut self, packet: Vec<u8>)
+    {
+        self.outgoing.borrow_mut().push_back(packet);
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>>
+    {
+        self.incoming.borrow_mut().pop_front()
+    }
+}
+
+/// The change to a single replicated component of an entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldChange
+{
+    Unchanged,
+    Set(Vec<u8>),
+    Removed,
+}
+
+unsafe impl CerealData for FieldChange {
+    fn write(&self, w: &mut ::std::io::Write) -> CerealResult<()> {
+        match *self {
+            FieldChange::Unchanged => 0u8.write(w),
+            FieldChange::Set(ref bytes) => {
+                try!(1u8.write(w));
+                bytes.write(w)
+            },
+            FieldChange::Removed => 2u8.write(w),
+        }
+    }
+
+    fn read(r: &mut ::std::io::Read) -> CerealResult<FieldChange> {
+        match try!(u8::read(r)) {
+            0 => Ok(FieldChange::Unchanged),
+            1 => Ok(FieldChange::Set(try!(CerealData::read(r)))),
+            2 => Ok(FieldChange::Removed),
+            x => Err(CerealError::Msg(format!("Unrecognized field change (Unchanged = 0, Set = 1, Removed = 2, Found {:?})", x))),
+        }
+    }
+}
+
+enum Update
+{
+    Changed(Entity, Vec<FieldChange>),
+    Removed(Entity),
+}
+
+unsafe impl CerealData for Update {
+    fn write(&self, w: &mut ::std::io::Write) -> CerealResult<()> {
+        match *self {
+            Update::Changed(entity, ref changes) => {
+                try!(1u8.write(w));
+                try!(entity.write(w));
+                changes.write(w)
+            },
+            Update::Removed(entity) => {
+                try!(2u8.write(w));
+                entity.write(w)
+            },
+        }
+    }
+
+    fn read(r: &mut ::std::io::Read) -> CerealResult<Update> {
+        match try!(u8::read(r)) {
+            1 => Ok(Update::Changed(try!(CerealData::read(r)), try!(CerealData::read(r)))),
+            2 => Ok(Update::Removed(try!(CerealData::read(r)))),
+            x => Err(CerealError::Msg(format!("Unrecognized update type (Changed = 1, Removed = 2, Found {:?})", x))),
+        }
+    }
+}
+
+/// Component managers with replicated component lists.
+///
+/// Implemented by `components!` for declarations with a `#[replicated(...)]` attribute.
+pub trait Replicated: ComponentManager
+{
+    #[doc(hidden)]
+    fn __encode(&self, index: usize) -> CerealResult<Vec<Option<Vec<u8>>>>;
+    #[doc(hidden)]
+    fn __decode(&mut self, entity: &IndexedEntity<Self>, changes: &[FieldChange]) -> CerealResult<()>;
+    /// Starts recording changes to the replicated lists, returning each list's version.
+    #[doc(hidden)]
+    fn __track_changes(&self) -> Vec<u64>;
+    /// Whether any replicated component at `index` may have changed since `versions`.
+    #[doc(hidden)]
+    fn __changed_since(&self, index: usize, versions: &[u64]) -> bool;
+}
+
+/// Identifies a client connected to a `ReplicationServer`.
+pub type ClientId = usize;
+
+struct Connection<T: Transport>
+{
+    transport: T,
+    sent: HashMap<Entity, Vec<Option<Vec<u8>>>>,
+    /// The versions of the replicated lists when the client was last updated.
+    seen: Option<Vec<u64>>,
+}
+
+/// Sends the changes to a world to each connected client.
+pub struct ReplicationServer<T: Transport>
+{
+    clients: Vec<Option<Connection<T>>>,
+}
+
+impl<T: Transport> ReplicationServer<T>
+{
+    pub fn new() -> ReplicationServer<T>
+    {
+        ReplicationServer {
+            clients: Vec::new(),
+        }
+    }
+
+    /// Adds a client. The whole world is sent to it on the next call to `replicate`.
+    pub fn connect(&mut self, transport: T) -> ClientId
+    {
+        let connection = Connection { transport, sent: HashMap::new(), seen: None };
+        match self.clients.iter().position(|c| c.is_none()) {
+            Some(id) => {
+                self.clients[id] = Some(connection);
+                id
+            },
+            None => {
+                self.clients.push(Some(connection));
+                self.clients.len() - 1
+            },
+        }
+    }
+
+    /// Removes a client, returning its transport.
+    pub fn disconnect(&mut self, client: ClientId) -> Option<T>
+    {
+        self.clients.get_mut(client).and_then(|c| c.take()).map(|c| c.transport)
+    }
+
+    /// Sends each client a packet with everything that changed since it was last sent one.
+    ///
+    /// The replicated lists record which components change from the first call onwards, so
+    /// only entities that are new to a client or have changed since its last update are encoded.
+    /// Clients with nothing to update aren't sent anything.
+    pub fn replicate<C, M>(&mut self, data: &DataHelper<C, M>) -> CerealResult<()>
+        where C: Replicated, M: ServiceManager
+    {
+        let versions = data.components.__track_changes();
+        let alive: HashSet<Entity> = data.entities().map(|e| **e).collect();
+        let mut encoded = HashMap::new();
+
+        for connection in self.clients.iter_mut().filter_map(|c| c.as_mut()) {
+            let mut updates = Vec::new();
+            for e in data.entities() {
+                let entity = **e;
+                let stale = match (connection.sent.contains_key(&entity), connection.seen.as_ref()) {
+                    (true, Some(seen)) => data.components.__changed_since(e.index(), seen),
+                    _ => true,
+                };
+                if !stale {
+                    continue;
+                }
+                let fields = match encoded.entry(entity) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(try!(data.components.__encode(e.index()))),
+                };
+                let changes = match connection.sent.get(&entity) {
+                    Some(sent) => fields.iter().zip(sent).map(|(field, sent)| {
+                        match (field, sent) {
+                            (field, sent) if field == sent => FieldChange::Unchanged,
+                            (Some(bytes), _) => FieldChange::Set(bytes.clone()),
+                            (None, _) => FieldChange::Removed,
+                        }
+                    }).collect(),
+                    None => fields.iter().map(|field| match *field {
+                        Some(ref bytes) => FieldChange::Set(bytes.clone()),
+                        None => FieldChange::Unchanged,
+                    }).collect::<Vec<_>>(),
+                };
+                if !connection.sent.contains_key(&entity) || changes.iter().any(|c| *c != FieldChange::Unchanged) {
+                    updates.push(Update::Changed(entity, changes));
+                    connection.sent.insert(entity, fields.clone());
+                }
+            }
+            connection.sent.retain(|entity, _| {
+                let kept = alive.contains(entity);
+                if !kept {
+                    updates.push(Update::Removed(*entity));
+                }
+                kept
+            });
+            connection.seen = Some(versions.clone());
+
+            if !updates.is_empty() {
+                let mut packet = Vec::new();
+                try!(updates.write(&mut packet));
+                connection.transport.send(packet);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Transport> Default for ReplicationServer<T>
+{
+    fn default() -> ReplicationServer<T>
+    {
+        ReplicationServer::new()
+    }
+}
+
+/// Applies changes sent by a `ReplicationServer` to a local world.
+pub struct ReplicationClient<T: Transport>
+{
+    transport: T,
+    entities: HashMap<Entity, Entity>,
+}
+
+impl<T: Transport> ReplicationClient<T>
+{
+    pub fn new(transport: T) -> ReplicationClient<T>
+    {
+        ReplicationClient {
+            transport,
+            entities: HashMap::new(),
+        }
+    }
+
+    /// Returns the local entity mirroring an entity on the server.
+    pub fn local_entity(&self, server: &Entity) -> Option<Entity>
+    {
+        self.entities.get(server).cloned()
+    }
+
+    /// Applies every packet received so far, returning how many there were.
+    ///
+    /// New entities are created through the queue, which is flushed after each packet that
+    /// creates any so that later packets can update them. Updated entities are modified (and so
+    /// reactivated) immediately. Updates to entities the client has already removed are ignored.
+    pub fn receive<S>(&mut self, world: &mut World<S>) -> CerealResult<usize>
+        where S: SystemManager, S::Components: Replicated
+    {
+        let mut count = 0;
+        while let Some(packet) = self.transport.receive() {
+            let updates: Vec<Update> = try!(CerealData::read(&mut &packet[..]));
+            let mut created = false;
+            for update in updates {
+                created |= try!(self.apply(world, update));
+            }
+            if created {
+                world.flush_queue();
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Applies a single update, returning whether it created an entity.
+    fn apply<S>(&mut self, world: &mut World<S>, update: Update) -> CerealResult<bool>
+        where S: SystemManager, S::Components: Replicated
+    {
+        match update {
+            Update::Changed(server, changes) => match self.entities.get(&server).cloned() {
+                Some(local) => {
+                    match world.with_entity_data(&local, |e, c| c.__decode(e.0, &changes)) {
+                        Some(result) => try!(result),
+                        None => return Ok(false),
+                    }
+                    world.modify_entity(local, ());
+                    Ok(false)
+                },
+                None => {
+                    let mut result = Ok(());
+                    let local = world.create_entity(|e: BuildData<S::Components>, c: &mut S::Components| {
+                        result = c.__decode(e.0, &changes);
+                    });
+                    self.entities.insert(server, local);
+                    result.map(|_| true)
+                },
+            },
+            Update::Removed(server) => {
+                let local = self.entities.remove(&server);
+                if let Some(local) = local.filter(|local| world.with_entity_data(local, |_, _| ()).is_some()) {
+                    world.remove_entity(local);
+                }
+                Ok(false)
+            },
+        }
+    }
+}
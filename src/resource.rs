@@ -0,0 +1,90 @@
+
+//! Singletons shared between systems, looked up by type.
+//!
+//! Every `DataHelper` has a `Resources` map alongside its services. Unlike services, resources
+//! don't have to be declared up front, so plugins can keep their own (eg: time, input state or
+//! asset handles) without editing a central struct:
+//!
+//! ```ignore
+//! world.resources.insert(Time { delta: 0.016 });
+//!
+//! // Later, from inside a system:
+//! let delta = data.resources.get::<Time>().unwrap().delta;
+//! ```
+//!
+//! Resources aren't part of a world's state as far as snapshots, deltas and saving are
+//! concerned. They're left alone by `World::restore` and start out empty after `World::load`.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A map holding at most one value of each type.
+///
+/// Values have to be `Send` and `Sync`, so that a `DataHelper` can still be shared between
+/// threads.
+#[derive(Default)]
+pub struct Resources
+{
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Resources
+{
+    pub fn new() -> Resources
+    {
+        Resources {
+            values: HashMap::new(),
+        }
+    }
+
+    /// Stores a resource, returning the one it replaced.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T>
+    {
+        self.values.insert(TypeId::of::<T>(), Box::new(value))
+            .map(|old| *old.downcast().unwrap())
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T>
+    {
+        self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+    }
+
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T>
+    {
+        self.values.get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut())
+    }
+
+    /// Returns the resource, inserting one made by `init` if there isn't one yet.
+    pub fn get_or_insert_with<T: Any + Send + Sync, F: FnOnce() -> T>(&mut self, init: F) -> &mut T
+    {
+        self.values.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(init()))
+            .downcast_mut()
+            .unwrap()
+    }
+
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T>
+    {
+        self.values.remove(&TypeId::of::<T>()).map(|value| *value.downcast().unwrap())
+    }
+
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool
+    {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.values.is_empty()
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.values.clear();
+    }
+}
@@ -0,0 +1,385 @@
+
+//! A bridge between the world and an embedded scripting language.
+//!
+//! This module doesn't contain a language itself. Instead, the language's bindings implement
+//! `ScriptEngine`, and scripts are given a `ScriptApi` through which they can create and remove
+//! entities, get and set the components registered as scriptable, and query entities by named
+//! aspects:
+//!
+//! ```ignore
+//! let mut registry = ScriptRegistry::new();
+//! registry.register("position", |c: &MyComponents| &c.position, |c| &mut c.position);
+//! registry.aspect("movers", aspect!(<MyComponents> all: [position, velocity]));
+//!
+//! world.add_system("scripts", ScriptSystem::new(engine, registry, &["update"]));
+//! ```
+//!
+//! Components set or removed by scripts go through `DataHelper::modify_entity`, so the entity's
+//! systems hear about the change on the next flush.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use {Aspect, BuildData, ComponentList, ComponentManager, DataHelper, Entity, EntityData};
+use {ModifyData, Process, ServiceManager, System};
+use component::Component;
+
+/// A value passed between scripts and the world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptValue
+{
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Entity(Entity),
+    List(Vec<ScriptValue>),
+}
+
+/// Types that can be converted to and from a `ScriptValue`.
+pub trait Scriptable: Sized
+{
+    fn to_script(&self) -> ScriptValue;
+    /// Returns `None` if the value has the wrong shape.
+    fn from_script(value: &ScriptValue) -> Option<Self>;
+}
+
+macro_rules! impl_scriptable {
+    ($ty:ty, $variant:ident, $inner:ty, $convert:expr) => {
+        impl Scriptable for $ty
+        {
+            fn to_script(&self) -> ScriptValue
+            {
+                ScriptValue::$variant(<$inner>::from(*self))
+            }
+
+            fn from_script(value: &ScriptValue) -> Option<$ty>
+            {
+                match *value {
+                    ScriptValue::$variant(inner) => $convert(inner),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+// Integers that don't fit are the wrong type rather than being truncated.
+impl_scriptable!(i32, Int, i64, |inner| i32::try_from(inner).ok());
+impl_scriptable!(i64, Int, i64, Some);
+impl_scriptable!(u32, Int, i64, |inner| u32::try_from(inner).ok());
+impl_scriptable!(f32, Float, f64, |inner| Some(inner as f32));
+impl_scriptable!(f64, Float, f64, Some);
+
+impl Scriptable for bool
+{
+    fn to_script(&self) -> ScriptValue
+    {
+        ScriptValue::Bool(*self)
+    }
+
+    fn from_script(value: &ScriptValue) -> Option<bool>
+    {
+        match *value {
+            ScriptValue::Bool(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl Scriptable for String
+{
+    fn to_script(&self) -> ScriptValue
+    {
+        ScriptValue::Str(self.clone())
+    }
+
+    fn from_script(value: &ScriptValue) -> Option<String>
+    {
+        match *value {
+            ScriptValue::Str(ref inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Scriptable for Entity
+{
+    fn to_script(&self) -> ScriptValue
+    {
+        ScriptValue::Entity(*self)
+    }
+
+    fn from_script(value: &ScriptValue) -> Option<Entity>
+    {
+        match *value {
+            ScriptValue::Entity(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Scriptable> Scriptable for Vec<T>
+{
+    fn to_script(&self) -> ScriptValue
+    {
+        ScriptValue::List(self.iter().map(Scriptable::to_script).collect())
+    }
+
+    fn from_script(value: &ScriptValue) -> Option<Vec<T>>
+    {
+        match *value {
+            ScriptValue::List(ref items) => items.iter().map(T::from_script).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Something a script asked for that couldn't be done.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptError
+{
+    UnknownComponent(String),
+    UnknownAspect(String),
+    /// The entity has been removed, or never existed.
+    InvalidEntity(Entity),
+    /// The value couldn't be converted to the component's type.
+    WrongType { component: String, value: ScriptValue },
+}
+
+impl fmt::Display for ScriptError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self {
+            ScriptError::UnknownComponent(ref name) => write!(f, "No scriptable component called `{}`", name),
+            ScriptError::UnknownAspect(ref name) => write!(f, "No aspect called `{}`", name),
+            ScriptError::InvalidEntity(entity) => write!(f, "{:?} doesn't exist", entity),
+            ScriptError::WrongType { ref component, ref value } =>
+                write!(f, "Can't set `{}` to {:?}", component, value),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+type Getter<C> = Box<dyn Fn(&C, &EntityData<C>) -> Option<ScriptValue> + Send + Sync>;
+type Setter<C> = Box<dyn Fn(&mut C, &ModifyData<C>, &ScriptValue) -> bool + Send + Sync>;
+type Remover<C> = Box<dyn Fn(&mut C, &ModifyData<C>) -> bool + Send + Sync>;
+
+struct Accessor<C: ComponentManager>
+{
+    get: Getter<C>,
+    set: Setter<C>,
+    remove: Remover<C>,
+}
+
+/// The components and aspects that scripts can use, by name.
+pub struct ScriptRegistry<C: ComponentManager>
+{
+    components: HashMap<String, Accessor<C>>,
+    aspects: HashMap<String, Aspect<C>>,
+}
+
+impl<C: ComponentManager> ScriptRegistry<C>
+{
+    pub fn new() -> ScriptRegistry<C>
+    {
+        ScriptRegistry {
+            components: HashMap::new(),
+            aspects: HashMap::new(),
+        }
+    }
+
+    /// Makes a component list available to scripts, replacing any registered under the same name.
+    ///
+    /// The list is found with the given accessors, such as `|c: &MyComponents| &c.position`, or
+    /// `HasComponent::list` and `HasComponent::list_mut` for code that's generic over `C`.
+    pub fn register<T>(&mut self, name: &str,
+        list: fn(&C) -> &ComponentList<C, T>, list_mut: fn(&mut C) -> &mut ComponentList<C, T>)
+        where T: Component + Scriptable
+    {
+        self.components.insert(name.to_string(), Accessor {
+            get: Box::new(move |c, e| list(c).__get(e.index()).map(Scriptable::to_script)),
+            set: Box::new(move |c, e, value| match T::from_script(value) {
+                Some(component) => {
+                    list_mut(c).set(e, component);
+                    true
+                },
+                None => false,
+            }),
+            remove: Box::new(move |c, e| list_mut(c).remove(e).is_some()),
+        });
+    }
+
+    /// Gives scripts a name to query entities with, replacing any aspect with the same name.
+    pub fn aspect(&mut self, name: &str, aspect: Aspect<C>)
+    {
+        self.aspects.insert(name.to_string(), aspect);
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool
+    {
+        self.components.contains_key(name)
+    }
+}
+
+impl<C: ComponentManager> Default for ScriptRegistry<C>
+{
+    fn default() -> ScriptRegistry<C>
+    {
+        ScriptRegistry::new()
+    }
+}
+
+/// The world, as seen by a script.
+pub struct ScriptApi<'a, C: ComponentManager + 'a, M: ServiceManager + 'a>
+{
+    data: &'a mut DataHelper<C, M>,
+    registry: &'a ScriptRegistry<C>,
+}
+
+impl<'a, C: ComponentManager, M: ServiceManager> ScriptApi<'a, C, M>
+{
+    pub fn new(data: &'a mut DataHelper<C, M>, registry: &'a ScriptRegistry<C>) -> ScriptApi<'a, C, M>
+    {
+        ScriptApi { data, registry }
+    }
+
+    /// The data itself, for anything the rest of the API doesn't cover.
+    pub fn data(&mut self) -> &mut DataHelper<C, M>
+    {
+        self.data
+    }
+
+    /// Creates an entity with no components. Systems hear about it on the next flush, so any
+    /// components set on it before then are taken into account.
+    pub fn create_entity(&mut self) -> Entity
+    {
+        self.data.create_entity(|_: BuildData<C>, _: &mut C| {})
+    }
+
+    pub fn remove_entity(&mut self, entity: Entity) -> Result<(), ScriptError>
+    {
+        self.data.with_entity_data(&entity, |_, _| ()).ok_or(ScriptError::InvalidEntity(entity))?;
+        self.data.remove_entity(entity);
+        Ok(())
+    }
+
+    /// The entity's component, or `Nil` if it doesn't have one.
+    pub fn get(&mut self, entity: Entity, component: &str) -> Result<ScriptValue, ScriptError>
+    {
+        let accessor = self.accessor(component)?;
+        self.data.with_entity_data(&entity, |e, c| (accessor.get)(c, &e).unwrap_or(ScriptValue::Nil))
+            .ok_or(ScriptError::InvalidEntity(entity))
+    }
+
+    pub fn set(&mut self, entity: Entity, component: &str, value: ScriptValue) -> Result<(), ScriptError>
+    {
+        let accessor = self.accessor(component)?;
+        match self.data.modify_entity(&entity, |e, c| (accessor.set)(c, &e, &value))
+        {
+            Some(true) => Ok(()),
+            Some(false) => Err(ScriptError::WrongType { component: component.to_string(), value }),
+            None => Err(ScriptError::InvalidEntity(entity)),
+        }
+    }
+
+    /// Removes the entity's component, returning whether it had one.
+    pub fn remove(&mut self, entity: Entity, component: &str) -> Result<bool, ScriptError>
+    {
+        let accessor = self.accessor(component)?;
+        self.data.modify_entity(&entity, |e, c| (accessor.remove)(c, &e))
+            .ok_or(ScriptError::InvalidEntity(entity))
+    }
+
+    /// Every entity matching the aspect registered under `aspect`, in order of entity id.
+    pub fn query(&self, aspect: &str) -> Result<Vec<Entity>, ScriptError>
+    {
+        let aspect = self.registry.aspects.get(aspect)
+            .ok_or_else(|| ScriptError::UnknownAspect(aspect.to_string()))?;
+        let mut entities: Vec<Entity> = self.data.entities()
+            .filter_map(|e| if aspect.check(&e, &self.data.components) { Some(**e) } else { None })
+            .collect();
+        entities.sort();
+        Ok(entities)
+    }
+
+    fn accessor(&self, component: &str) -> Result<&'a Accessor<C>, ScriptError>
+    {
+        self.registry.components.get(component).ok_or_else(|| ScriptError::UnknownComponent(component.to_string()))
+    }
+}
+
+/// The bindings to a scripting language.
+pub trait ScriptEngine<C: ComponentManager, M: ServiceManager>
+{
+    type Error;
+    /// Calls a script function, giving it access to the world through `api`.
+    fn call(&mut self, function: &str, args: &[ScriptValue], api: &mut ScriptApi<C, M>)
+        -> Result<ScriptValue, Self::Error>;
+}
+
+/// Calls script functions each update, in order.
+///
+/// Errors don't stop the remaining functions from being called, and are kept until taken with
+/// `take_errors`. Adding the system with `World::add_system` needs the engine to be `Send`;
+/// engines that aren't can still be used from `systems!`.
+pub struct ScriptSystem<C, M, E> where C: ComponentManager, M: ServiceManager, E: ScriptEngine<C, M>
+{
+    pub engine: E,
+    pub registry: ScriptRegistry<C>,
+    functions: Vec<String>,
+    errors: Vec<(String, E::Error)>,
+    _marker: PhantomData<M>,
+}
+
+impl<C, M, E> ScriptSystem<C, M, E> where C: ComponentManager, M: ServiceManager, E: ScriptEngine<C, M>
+{
+    pub fn new(engine: E, registry: ScriptRegistry<C>, functions: &[&str]) -> ScriptSystem<C, M, E>
+    {
+        ScriptSystem {
+            engine,
+            registry,
+            functions: functions.iter().map(|f| f.to_string()).collect(),
+            errors: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Calls a single script function straight away.
+    pub fn call(&mut self, function: &str, args: &[ScriptValue], data: &mut DataHelper<C, M>)
+        -> Result<ScriptValue, E::Error>
+    {
+        self.engine.call(function, args, &mut ScriptApi::new(data, &self.registry))
+    }
+
+    /// The errors returned by each function since they were last taken, along with its name.
+    pub fn take_errors(&mut self) -> Vec<(String, E::Error)>
+    {
+        ::std::mem::take(&mut self.errors)
+    }
+}
+
+impl<C, M, E> System for ScriptSystem<C, M, E> where C: ComponentManager, M: ServiceManager, E: ScriptEngine<C, M>
+{
+    type Components = C;
+    type Services = M;
+}
+
+impl<C, M, E> Process for ScriptSystem<C, M, E> where C: ComponentManager, M: ServiceManager, E: ScriptEngine<C, M>
+{
+    fn process(&mut self, data: &mut DataHelper<C, M>)
+    {
+        for function in &self.functions {
+            let result = self.engine.call(function, &[], &mut ScriptApi::new(data, &self.registry));
+            if let Err(err) = result {
+                self.errors.push((function.clone(), err));
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Systems added to a world after it's been created.
+
+use DataHelper;
+use EntityData;
+use {ComponentManager, ServiceManager, SystemManager};
+use Process;
+
+/// A boxed system, as held by `DynamicSystems`.
+///
+/// Systems have to be `Send`, so that a world with dynamic systems can still be sent to another
+/// thread.
+pub type BoxedProcess<C, M> = Box<dyn Process<Components=C, Services=M> + Send>;
+
+/// Named systems, updated in the order they were added.
+///
+/// Each world has one in `World::dynamic_systems`, which is updated after the systems from
+/// `systems!`. Systems should be added and removed with `World::add_system` and
+/// `World::remove_system`, so they hear about the entities that already exist.
+pub struct DynamicSystems<C: ComponentManager, M: ServiceManager>
+{
+    systems: Vec<(String, BoxedProcess<C, M>)>,
+}
+
+impl<C: ComponentManager, M: ServiceManager> DynamicSystems<C, M>
+{
+    pub fn new() -> DynamicSystems<C, M>
+    {
+        DynamicSystems {
+            systems: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.systems.is_empty()
+    }
+
+    /// The names of the systems, in the order they're updated.
+    pub fn names(&self) -> Vec<&str>
+    {
+        self.systems.iter().map(|(name, _)| &name[..]).collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool
+    {
+        self.position(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BoxedProcess<C, M>>
+    {
+        self.position(name).map(|i| &self.systems[i].1)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut BoxedProcess<C, M>>
+    {
+        match self.position(name)
+        {
+            Some(i) => Some(&mut self.systems[i].1),
+            None => None,
+        }
+    }
+
+    /// Adds a system to the end of the list, returning it back if the name is already taken.
+    pub fn push(&mut self, name: &str, system: BoxedProcess<C, M>) -> Result<(), BoxedProcess<C, M>>
+    {
+        if self.contains(name) {
+            return Err(system);
+        }
+        self.systems.push((name.to_string(), system));
+        Ok(())
+    }
+
+    /// Takes a system out of the list, keeping the order of the rest.
+    pub fn remove(&mut self, name: &str) -> Option<BoxedProcess<C, M>>
+    {
+        self.position(name).map(|i| self.systems.remove(i).1)
+    }
+
+    fn position(&self, name: &str) -> Option<usize>
+    {
+        self.systems.iter().position(|(other, _)| other == name)
+    }
+}
+
+impl<C: ComponentManager, M: ServiceManager> Default for DynamicSystems<C, M>
+{
+    fn default() -> DynamicSystems<C, M>
+    {
+        DynamicSystems::new()
+    }
+}
+
+impl<C: ComponentManager, M: ServiceManager> SystemManager for DynamicSystems<C, M>
+{
+    type Components = C;
+    type Services = M;
+    fn __new() -> DynamicSystems<C, M>
+    {
+        DynamicSystems::new()
+    }
+
+    fn __activated(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for (_, system) in &mut self.systems {
+            system.activated(&en, co, se);
+        }
+    }
+
+    fn __reactivated(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for (_, system) in &mut self.systems {
+            system.reactivated(&en, co, se);
+        }
+    }
+
+    fn __deactivated(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        for (_, system) in &mut self.systems {
+            system.deactivated(&en, co, se);
+        }
+    }
+
+    fn __update(&mut self, data: &mut DataHelper<C, M>)
+    {
+        for (_, system) in &mut self.systems {
+            system.process(data);
+        }
+    }
+}
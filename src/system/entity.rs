@@ -100,6 +100,6 @@ impl<T: EntityProcess> Process for EntitySystem<T>
 {
     fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
     {
-        self.inner.process(EntityIter::Map(self.interested.values()), c);
+        self.inner.process(EntityIter::__from_map(&self.interested, c.is_deterministic()), c);
     }
 }
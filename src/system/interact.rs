@@ -1,11 +1,14 @@
 
-//! System to specifically deal with interactions between two types of entity.
+//! Systems to specifically deal with interactions between different types of entity.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::vec;
 
 use Aspect;
 use DataHelper;
+use {ComponentManager, ServiceManager};
 use {Entity, IndexedEntity};
 use EntityData;
 use EntityIter;
@@ -16,13 +19,118 @@ pub trait InteractProcess: System
     fn process<'a>(&mut self, EntityIter<'a, Self::Components>, EntityIter<'a, Self::Components>, &mut DataHelper<Self::Components, Self::Services>);
 }
 
+/// The entities matching each of the two aspects of an interaction.
+///
+/// Without a second aspect, both sides of the interaction are the same set of entities.
+struct Interested<C: ComponentManager>
+{
+    a: HashMap<Entity, IndexedEntity<C>>,
+    b: HashMap<Entity, IndexedEntity<C>>,
+    aspect_a: Aspect<C>,
+    aspect_b: Option<Aspect<C>>,
+}
+
+impl<C: ComponentManager> Interested<C>
+{
+    fn new(aspect_a: Aspect<C>, aspect_b: Option<Aspect<C>>) -> Interested<C>
+    {
+        Interested
+        {
+            a: HashMap::new(),
+            b: HashMap::new(),
+            aspect_a,
+            aspect_b,
+        }
+    }
+
+    fn b(&self) -> &HashMap<Entity, IndexedEntity<C>>
+    {
+        if self.aspect_b.is_some() { &self.b } else { &self.a }
+    }
+
+    /// Whether the pair `(y, x)` is also visited, making `(x, y)` the duplicate of the two.
+    fn is_mirrored(&self, x: &Entity, y: &Entity) -> bool
+    {
+        x > y && self.a.contains_key(y) && self.b().contains_key(x)
+    }
+
+    fn activated<S, M>(&mut self, inner: &mut S, entity: &EntityData<C>, components: &C, services: &mut M)
+        where S: System<Components=C, Services=M>, M: ServiceManager
+    {
+        if self.aspect_a.check(entity, components)
+        {
+            self.a.insert(***entity, (**entity).__clone());
+            inner.activated(entity, components, services);
+        }
+        if self.aspect_b.as_ref().is_some_and(|aspect| aspect.check(entity, components))
+        {
+            self.b.insert(***entity, (**entity).__clone());
+            inner.activated(entity, components, services);
+        }
+    }
+
+    fn reactivated<S, M>(&mut self, inner: &mut S, entity: &EntityData<C>, components: &C, services: &mut M)
+        where S: System<Components=C, Services=M>, M: ServiceManager
+    {
+        if self.a.contains_key(entity)
+        {
+            if self.aspect_a.check(entity, components)
+            {
+                inner.reactivated(entity, components, services);
+            }
+            else
+            {
+                self.a.remove(entity);
+                inner.deactivated(entity, components, services);
+            }
+        }
+        else if self.aspect_a.check(entity, components)
+        {
+            self.a.insert(***entity, (**entity).__clone());
+            inner.activated(entity, components, services);
+        }
+        let aspect_b = match self.aspect_b
+        {
+            Some(ref aspect) => aspect,
+            None => return,
+        };
+        if self.b.contains_key(entity)
+        {
+            if aspect_b.check(entity, components)
+            {
+                inner.reactivated(entity, components, services);
+            }
+            else
+            {
+                self.b.remove(entity);
+                inner.deactivated(entity, components, services);
+            }
+        }
+        else if aspect_b.check(entity, components)
+        {
+            self.b.insert(***entity, (**entity).__clone());
+            inner.activated(entity, components, services);
+        }
+    }
+
+    fn deactivated<S, M>(&mut self, inner: &mut S, entity: &EntityData<C>, components: &C, services: &mut M)
+        where S: System<Components=C, Services=M>, M: ServiceManager
+    {
+        if self.a.remove(entity).is_some()
+        {
+            inner.deactivated(entity, components, services);
+        }
+        if self.b.remove(entity).is_some()
+        {
+            inner.deactivated(entity, components, services);
+        }
+    }
+}
+
 pub struct InteractSystem<T: InteractProcess>
 {
     pub inner: T,
-    interested_a: HashMap<Entity, IndexedEntity<T::Components>>,
-    interested_b: HashMap<Entity, IndexedEntity<T::Components>>,
-    aspect_a: Aspect<T::Components>,
-    aspect_b: Aspect<T::Components>,
+    interested: Interested<T::Components>,
 }
 
 impl<T: InteractProcess> Deref for InteractSystem<T>
@@ -48,11 +156,21 @@ impl<T: InteractProcess> InteractSystem<T>
     {
         InteractSystem
         {
-            interested_a: HashMap::new(),
-            interested_b: HashMap::new(),
-            aspect_a: aspect_a,
-            aspect_b: aspect_b,
-            inner: inner,
+            interested: Interested::new(aspect_a, Some(aspect_b)),
+            inner,
+        }
+    }
+
+    /// An interaction between entities matching the same aspect.
+    ///
+    /// Both iterators given to `process` go over the one set of entities, and the inner system
+    /// only hears about each entity once. Use `Pairs::new` to visit each pair of them once.
+    pub fn same(inner: T, aspect: Aspect<T::Components>) -> InteractSystem<T>
+    {
+        InteractSystem
+        {
+            interested: Interested::new(aspect, None),
+            inner,
         }
     }
 }
@@ -63,73 +181,389 @@ impl<T: InteractProcess> System for InteractSystem<T>
     type Services = T::Services;
     fn activated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.aspect_a.check(entity, components)
+        self.interested.activated(&mut self.inner, entity, components, services);
+    }
+
+    fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        self.interested.reactivated(&mut self.inner, entity, components, services);
+    }
+
+    fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        self.interested.deactivated(&mut self.inner, entity, components, services);
+    }
+}
+
+impl<T: InteractProcess> Process for InteractSystem<T>
+{
+    fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
+    {
+        let sorted = c.is_deterministic();
+        self.inner.process(EntityIter::__from_map(&self.interested.a, sorted), EntityIter::__from_map(self.interested.b(), sorted), c);
+    }
+}
+
+pub trait MultiInteractProcess<const N: usize>: System
+{
+    /// Processes the entities matching each aspect, in the order the aspects were given.
+    fn process<'a>(&mut self, groups: [EntityIter<'a, Self::Components>; N], data: &mut DataHelper<Self::Components, Self::Services>);
+}
+
+/// Like `InteractSystem`, but with any number of aspects, such as projectiles, targets and
+/// shields:
+///
+/// ```ignore
+/// MultiInteractSystem::new(Combat, [
+///     aspect!(<MyComponents> all: [projectile]),
+///     aspect!(<MyComponents> all: [health]),
+///     aspect!(<MyComponents> all: [shield]),
+/// ])
+/// ```
+///
+/// Each entity is activated in the inner system once for every aspect it matches.
+pub struct MultiInteractSystem<T: MultiInteractProcess<N>, const N: usize>
+{
+    pub inner: T,
+    interested: [HashMap<Entity, IndexedEntity<T::Components>>; N],
+    aspects: [Aspect<T::Components>; N],
+}
+
+impl<T: MultiInteractProcess<N>, const N: usize> Deref for MultiInteractSystem<T, N>
+{
+    type Target = T;
+    fn deref(&self) -> &T
+    {
+        &self.inner
+    }
+}
+
+impl<T: MultiInteractProcess<N>, const N: usize> DerefMut for MultiInteractSystem<T, N>
+{
+    fn deref_mut(&mut self) -> &mut T
+    {
+        &mut self.inner
+    }
+}
+
+impl<T: MultiInteractProcess<N>, const N: usize> MultiInteractSystem<T, N>
+{
+    pub fn new(inner: T, aspects: [Aspect<T::Components>; N]) -> MultiInteractSystem<T, N>
+    {
+        MultiInteractSystem
         {
-            self.interested_a.insert(***entity, (**entity).__clone());
-            self.inner.activated(entity, components, services);
+            inner,
+            interested: [(); N].map(|_| HashMap::new()),
+            aspects,
         }
-        if self.aspect_b.check(entity, components)
+    }
+}
+
+impl<T: MultiInteractProcess<N>, const N: usize> System for MultiInteractSystem<T, N>
+{
+    type Components = T::Components;
+    type Services = T::Services;
+    fn activated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        for (interested, aspect) in self.interested.iter_mut().zip(&self.aspects)
         {
-            self.interested_b.insert(***entity, (**entity).__clone());
-            self.inner.activated(entity, components, services);
+            if aspect.check(entity, components)
+            {
+                interested.insert(***entity, (**entity).__clone());
+                self.inner.activated(entity, components, services);
+            }
         }
     }
 
     fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
     {
-        if self.interested_a.contains_key(entity)
+        for (interested, aspect) in self.interested.iter_mut().zip(&self.aspects)
         {
-            if self.aspect_a.check(entity, components)
+            if interested.contains_key(entity)
             {
-                self.inner.reactivated(entity, components, services);
+                if aspect.check(entity, components)
+                {
+                    self.inner.reactivated(entity, components, services);
+                }
+                else
+                {
+                    interested.remove(entity);
+                    self.inner.deactivated(entity, components, services);
+                }
             }
-            else
+            else if aspect.check(entity, components)
             {
-                self.interested_a.remove(entity);
-                self.inner.deactivated(entity, components, services);
+                interested.insert(***entity, (**entity).__clone());
+                self.inner.activated(entity, components, services);
             }
         }
-        else if self.aspect_a.check(entity, components)
-        {
-            self.interested_a.insert(***entity, (**entity).__clone());
-            self.inner.activated(entity, components, services);
-        }
-        if self.interested_b.contains_key(entity)
+    }
+
+    fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        for interested in &mut self.interested
         {
-            if self.aspect_b.check(entity, components)
-            {
-                self.inner.reactivated(entity, components, services);
-            }
-            else
+            if interested.remove(entity).is_some()
             {
-                self.interested_b.remove(entity);
                 self.inner.deactivated(entity, components, services);
             }
         }
-        else if self.aspect_b.check(entity, components)
+    }
+}
+
+impl<T: MultiInteractProcess<N>, const N: usize> Process for MultiInteractSystem<T, N>
+{
+    fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
+    {
+        let sorted = c.is_deterministic();
+        self.inner.process(self.interested.each_ref().map(|interested| EntityIter::__from_map(interested, sorted)), c);
+    }
+}
+
+pub trait SpatialProcess: System
+{
+    fn process<'a>(&mut self, pairs: Pairs<'a, Self::Components>, data: &mut DataHelper<Self::Components, Self::Services>);
+}
+
+/// Pairs of entities, one from each side of an interaction.
+///
+/// An entity is never paired with itself, and each pair is only visited once even when both
+/// entities are on both sides.
+pub struct Pairs<'a, C: ComponentManager>
+{
+    inner: PairsInner<'a, C>,
+}
+
+enum PairsInner<'a, C: ComponentManager>
+{
+    /// Every pair of entities from `a` and `b`, worked out as they're visited.
+    Cross
+    {
+        a: Vec<&'a IndexedEntity<C>>,
+        b: Vec<&'a IndexedEntity<C>>,
+        in_a: BTreeSet<Entity>,
+        in_b: BTreeSet<Entity>,
+        next: (usize, usize),
+    },
+    /// Pairs that have already been picked out, such as by a `SpatialInteractSystem`.
+    Listed(vec::IntoIter<(&'a IndexedEntity<C>, &'a IndexedEntity<C>)>),
+}
+
+impl<'a, C: ComponentManager> Pairs<'a, C>
+{
+    /// Pairs each entity from `a` with each from `b`, such as the iterators given to an
+    /// `InteractProcess`.
+    pub fn new(a: EntityIter<'a, C>, b: EntityIter<'a, C>) -> Pairs<'a, C>
+    {
+        let a: Vec<&'a IndexedEntity<C>> = a.map(|e| e.0).collect();
+        let b: Vec<&'a IndexedEntity<C>> = b.map(|e| e.0).collect();
+        Pairs
         {
-            self.interested_b.insert(***entity, (**entity).__clone());
-            self.inner.activated(entity, components, services);
+            inner: PairsInner::Cross
+            {
+                in_a: a.iter().map(|e| ***e).collect(),
+                in_b: b.iter().map(|e| ***e).collect(),
+                a,
+                b,
+                next: (0, 0),
+            },
         }
     }
+}
 
-    fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+impl<'a, C: ComponentManager> Iterator for Pairs<'a, C>
+{
+    type Item = (EntityData<'a, C>, EntityData<'a, C>);
+    fn next(&mut self) -> Option<(EntityData<'a, C>, EntityData<'a, C>)>
     {
-        if self.interested_a.remove(entity).is_some()
+        match self.inner
         {
-            self.inner.deactivated(entity, components, services);
+            PairsInner::Cross { ref a, ref b, ref in_a, ref in_b, ref mut next } => {
+                while next.0 < a.len() && !b.is_empty() {
+                    let (x, y) = (a[next.0], b[next.1]);
+                    *next = if next.1 + 1 < b.len() { (next.0, next.1 + 1) } else { (next.0 + 1, 0) };
+                    if **x != **y && !(**x > **y && in_a.contains(y) && in_b.contains(x)) {
+                        return Some((EntityData(x), EntityData(y)));
+                    }
+                }
+                None
+            },
+            PairsInner::Listed(ref mut pairs) => pairs.next().map(|(a, b)| (EntityData(a), EntityData(b))),
         }
-        if self.interested_b.remove(entity).is_some()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>)
+    {
+        match self.inner
         {
-            self.inner.deactivated(entity, components, services);
+            PairsInner::Cross { ref a, ref b, next, .. } => (0, Some((a.len() - next.0) * b.len() - next.1)),
+            PairsInner::Listed(ref pairs) => pairs.size_hint(),
         }
     }
 }
 
-impl<T: InteractProcess> Process for InteractSystem<T>
+type PositionFn<C> = Box<dyn Fn(EntityData<C>, &C) -> (f32, f32) + Send + Sync>;
+type Cell = Vec<(Entity, (f32, f32))>;
+
+/// Like `InteractSystem`, but only passes on pairs of entities that are within `radius` of
+/// each other.
+///
+/// Each update, the entities matching the second aspect are sorted into a grid of cells
+/// `radius` wide, so each entity from the first aspect is only checked against those in
+/// neighbouring cells. As with `Pairs`, an entity is never paired with itself, and each pair is
+/// only passed on once.
+pub struct SpatialInteractSystem<T: SpatialProcess>
+{
+    pub inner: T,
+    interested: Interested<T::Components>,
+    position: PositionFn<T::Components>,
+    radius: f32,
+    grid: HashMap<(i32, i32), Cell>,
+    /// Emptied cells, kept to be reused rather than allocating new ones.
+    spare: Vec<Cell>,
+}
+
+impl<T: SpatialProcess> Deref for SpatialInteractSystem<T>
+{
+    type Target = T;
+    fn deref(&self) -> &T
+    {
+        &self.inner
+    }
+}
+
+impl<T: SpatialProcess> DerefMut for SpatialInteractSystem<T>
+{
+    fn deref_mut(&mut self) -> &mut T
+    {
+        &mut self.inner
+    }
+}
+
+impl<T: SpatialProcess> SpatialInteractSystem<T>
+{
+    /// `position` finds where an entity is, such as `|e, c| (c.position[e].x, c.position[e].y)`.
+    ///
+    /// Panics if `radius` isn't positive.
+    pub fn new<F>(inner: T, aspect_a: Aspect<T::Components>, aspect_b: Aspect<T::Components>, radius: f32, position: F)
+        -> SpatialInteractSystem<T>
+        where F: Fn(EntityData<T::Components>, &T::Components) -> (f32, f32) + Send + Sync + 'static
+    {
+        check_radius(radius);
+        SpatialInteractSystem
+        {
+            inner,
+            interested: Interested::new(aspect_a, Some(aspect_b)),
+            position: Box::new(position),
+            radius,
+            grid: HashMap::new(),
+            spare: Vec::new(),
+        }
+    }
+
+    /// An interaction between entities matching the same aspect, such as things bumping into
+    /// each other.
+    ///
+    /// Panics if `radius` isn't positive.
+    pub fn same<F>(inner: T, aspect: Aspect<T::Components>, radius: f32, position: F) -> SpatialInteractSystem<T>
+        where F: Fn(EntityData<T::Components>, &T::Components) -> (f32, f32) + Send + Sync + 'static
+    {
+        check_radius(radius);
+        SpatialInteractSystem
+        {
+            inner,
+            interested: Interested::new(aspect, None),
+            position: Box::new(position),
+            radius,
+            grid: HashMap::new(),
+            spare: Vec::new(),
+        }
+    }
+
+    pub fn radius(&self) -> f32
+    {
+        self.radius
+    }
+
+    /// Panics if `radius` isn't positive.
+    pub fn set_radius(&mut self, radius: f32)
+    {
+        check_radius(radius);
+        self.radius = radius;
+    }
+
+    fn cell(&self, (x, y): (f32, f32)) -> (i32, i32)
+    {
+        ((x / self.radius).floor() as i32, (y / self.radius).floor() as i32)
+    }
+}
+
+fn check_radius(radius: f32)
+{
+    assert!(radius > 0.0, "The radius of a SpatialInteractSystem must be positive, not {}", radius);
+}
+
+impl<T: SpatialProcess> System for SpatialInteractSystem<T>
+{
+    type Components = T::Components;
+    type Services = T::Services;
+    fn activated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        self.interested.activated(&mut self.inner, entity, components, services);
+    }
+
+    fn reactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        self.interested.reactivated(&mut self.inner, entity, components, services);
+    }
+
+    fn deactivated(&mut self, entity: &EntityData<T::Components>, components: &T::Components, services: &mut T::Services)
+    {
+        self.interested.deactivated(&mut self.inner, entity, components, services);
+    }
+}
+
+impl<T: SpatialProcess> Process for SpatialInteractSystem<T>
 {
     fn process(&mut self, c: &mut DataHelper<T::Components, T::Services>)
     {
-        self.inner.process(EntityIter::Map(self.interested_a.values()), EntityIter::Map(self.interested_b.values()), c);
+        // Cells used last update are cleared and kept where they are, while the rest are taken
+        // out of the grid and set aside, so that the grid doesn't grow as entities move around
+        // and new cells can reuse the old ones' allocations.
+        let spare = &mut self.spare;
+        self.grid.retain(|_, cell| {
+            if cell.is_empty() {
+                spare.push(mem::take(cell));
+                false
+            } else {
+                cell.clear();
+                true
+            }
+        });
+        let sorted = c.is_deterministic();
+        for b in EntityIter::__from_map(self.interested.b(), sorted) {
+            let b = b.0;
+            let position = (self.position)(EntityData(b), &c.components);
+            let cell = self.cell(position);
+            let spare = &mut self.spare;
+            self.grid.entry(cell).or_insert_with(|| spare.pop().unwrap_or_default()).push((**b, position));
+        }
+
+        let radius_squared = self.radius * self.radius;
+        let mut pairs = Vec::new();
+        for a in EntityIter::__from_map(&self.interested.a, sorted) {
+            let a = a.0;
+            let (x, y) = (self.position)(EntityData(a), &c.components);
+            let (cx, cy) = self.cell((x, y));
+            for cell in (cx.saturating_sub(1)..=cx.saturating_add(1)).flat_map(|i| (cy.saturating_sub(1)..=cy.saturating_add(1)).map(move |j| (i, j))) {
+                for &(b, (bx, by)) in self.grid.get(&cell).into_iter().flatten() {
+                    let (dx, dy) = (bx - x, by - y);
+                    if **a != b && !self.interested.is_mirrored(a, &b) && dx * dx + dy * dy <= radius_squared {
+                        pairs.push((a, &self.interested.b()[&b]));
+                    }
+                }
+            }
+        }
+        self.inner.process(Pairs { inner: PairsInner::Listed(pairs.into_iter()) }, c);
     }
 }
@@ -1,8 +1,10 @@
 
 //! Types to process the world and entities.
 
+pub use self::dynamic::{DynamicSystems};
 pub use self::entity::{EntitySystem, EntityProcess};
-pub use self::interact::{InteractSystem, InteractProcess};
+pub use self::interact::{InteractSystem, InteractProcess, MultiInteractSystem, MultiInteractProcess};
+pub use self::interact::{SpatialInteractSystem, SpatialProcess};
 pub use self::interval::{IntervalSystem};
 pub use self::lazy::{LazySystem};
 
@@ -11,6 +13,7 @@ use ComponentManager;
 use ServiceManager;
 use DataHelper;
 
+pub mod dynamic;
 pub mod entity;
 pub mod interact;
 pub mod interval;
@@ -48,3 +51,33 @@ pub trait Process: System
     /// Process the world.
     fn process(&mut self, &mut DataHelper<Self::Components, Self::Services>);
 }
+
+/// Lets a boxed system, such as one taken back out of a world with `World::remove_system`, be
+/// used wherever a system is expected.
+impl<P: System + ?Sized> System for Box<P>
+{
+    type Components = P::Components;
+    type Services = P::Services;
+    fn activated(&mut self, e: &EntityData<Self::Components>, c: &Self::Components, s: &mut Self::Services)
+    {
+        (**self).activated(e, c, s);
+    }
+
+    fn reactivated(&mut self, e: &EntityData<Self::Components>, c: &Self::Components, s: &mut Self::Services)
+    {
+        (**self).reactivated(e, c, s);
+    }
+
+    fn deactivated(&mut self, e: &EntityData<Self::Components>, c: &Self::Components, s: &mut Self::Services)
+    {
+        (**self).deactivated(e, c, s);
+    }
+}
+
+impl<P: Process + ?Sized> Process for Box<P>
+{
+    fn process(&mut self, data: &mut DataHelper<Self::Components, Self::Services>)
+    {
+        (**self).process(data);
+    }
+}
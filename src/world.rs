@@ -2,16 +2,34 @@
 #[cfg(feature="serialisation")] use cereal::{CerealData, CerealResult};
 #[cfg(feature="serialisation")] use std::io::{Read, Write};
 
+use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
-use {EntityData, ModifyData};
+use Aspect;
+use {BuildData, EntityData, ModifyData};
 use {Entity, IndexedEntity, EntityIter};
+use entity::EntityMap;
 use {EntityBuilder, EntityModifier};
+use component::{Component, ComponentList, HasComponent};
+use plugin::{HasGroup, HasService};
 use entity::EntityManager;
+use delta::{Delta, DiffComponents};
+use dynamic::DynamicComponents;
+use resource::Resources;
+use groups::{EntityGroup, EntityGroups};
+use names::{Names, Tag};
+use observer::{ObserverId, Observers, Trigger};
+use system::Process;
+use system::dynamic::{BoxedProcess, DynamicSystems};
 
 pub struct World<S> where S: SystemManager
 {
     pub systems: S,
+    /// Systems added with `add_system`, which are updated after `systems`.
+    pub dynamic_systems: DynamicSystems<S::Components, S::Services>,
+    /// Callbacks added with `add_observer`, which hear about entities after the systems.
+    pub observers: Observers<S::Components, S::Services>,
     pub data: DataHelper<S::Components, S::Services>,
 }
 
@@ -19,15 +37,209 @@ pub struct DataHelper<C, M> where C: ComponentManager, M: ServiceManager
 {
     pub components: C,
     pub services: M,
+    /// Singletons looked up by type, which aren't included in snapshots or saves.
+    pub resources: Resources,
+    /// Named groups of entities, added with `World::add_entity_group`.
+    pub groups: EntityGroups<C>,
     entities: EntityManager<C>,
 }
 
+/// An in-memory copy of a `DataHelper`'s components, services and entities.
+///
+/// Taken with `DataHelper::snapshot` and applied with `World::restore`. Resources aren't
+/// included, so restoring leaves them as they are.
+pub struct Snapshot<C, M> where C: ComponentManager, M: ServiceManager
+{
+    components: C,
+    services: M,
+    entities: EntityManager<C>,
+}
+
+impl<C: ComponentManager, M: ServiceManager> Clone for Snapshot<C, M> where C: Clone, M: Clone
+{
+    fn clone(&self) -> Snapshot<C, M>
+    {
+        Snapshot {
+            components: self.components.clone(),
+            services: self.services.clone(),
+            entities: self.entities.clone(),
+        }
+    }
+}
+
+/// Keeps the most recent snapshots of a world, discarding the oldest once full.
+pub struct SnapshotBuffer<C, M> where C: ComponentManager, M: ServiceManager
+{
+    frames: VecDeque<Snapshot<C, M>>,
+    capacity: usize,
+}
+
+impl<C: ComponentManager, M: ServiceManager> SnapshotBuffer<C, M>
+{
+    /// Creates a buffer holding at most `capacity` snapshots.
+    pub fn new(capacity: usize) -> SnapshotBuffer<C, M>
+    {
+        SnapshotBuffer {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds a snapshot as the most recent frame.
+    pub fn push(&mut self, snapshot: Snapshot<C, M>)
+    {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(snapshot);
+    }
+
+    /// Returns the snapshot taken `age` frames before the most recent one.
+    pub fn get(&self, age: usize) -> Option<&Snapshot<C, M>>
+    {
+        if age < self.frames.len() {
+            self.frames.get(self.frames.len() - 1 - age)
+        } else {
+            None
+        }
+    }
+
+    /// Discards the `age` most recent snapshots, returning the one that is now the most recent.
+    ///
+    /// Returns `None` and leaves the buffer untouched if it doesn't go back that far.
+    pub fn rewind(&mut self, age: usize) -> Option<&Snapshot<C, M>>
+    {
+        if age >= self.frames.len() {
+            return None;
+        }
+        let len = self.frames.len() - age;
+        self.frames.truncate(len);
+        self.frames.back()
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.frames.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize
+    {
+        self.capacity
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.frames.clear();
+    }
+}
+
+/// A single entity with its components, name and tags, taken out of a world with
+/// `World::take_entity`.
+///
+/// Bundles can be held onto, sent elsewhere (with the `serialisation` feature), and put into any
+/// world with the same systems using `World::insert_entity`.
+pub struct EntityBundle<C> where C: ComponentManager
+{
+    origin: Entity,
+    local: Entity,
+    components: C,
+    entities: EntityManager<C>,
+}
+
+impl<C: ComponentManager> EntityBundle<C>
+{
+    fn new(origin: Entity) -> EntityBundle<C>
+    {
+        let mut entities = EntityManager::new();
+        EntityBundle {
+            origin,
+            local: entities.create(),
+            components: C::__new(),
+            entities,
+        }
+    }
+
+    /// The identifier the entity had in the world it was taken from.
+    pub fn entity(&self) -> Entity
+    {
+        self.origin
+    }
+
+    pub fn name(&self) -> Option<&str>
+    {
+        self.entities.names().name(&self.local)
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item=&str> + '_
+    {
+        let names = self.entities.names();
+        names.tags_of(&self.local).map(move |tag| names.tag_name(tag))
+    }
+
+    /// Gives access to the entity's components while it's in the bundle.
+    pub fn with_entity_data<F, R>(&mut self, call: F) -> R
+        where F: FnOnce(EntityData<C>, &mut C) -> R
+    {
+        call(EntityData(self.entities.indexed(&self.local)), &mut self.components)
+    }
+}
+
+#[cfg(feature="serialisation")]
+unsafe impl<C: ComponentManager> CerealData for EntityBundle<C> where C: CerealData {
+    fn write(&self, w: &mut Write) -> CerealResult<()> {
+        try!(self.origin.write(w));
+        try!(self.local.write(w));
+        try!(self.entities.write(w));
+        self.components.write(w)
+    }
+
+    fn read(r: &mut Read) -> CerealResult<Self> {
+        Ok(EntityBundle {
+            origin: try!(CerealData::read(r)),
+            local: try!(CerealData::read(r)),
+            entities: try!(CerealData::read(r)),
+            components: try!(CerealData::read(r)),
+        })
+    }
+}
+
 pub trait ComponentManager: 'static+Sized
 {
     #[doc(hidden)]
     fn __new() -> Self;
     #[doc(hidden)]
     fn __remove_all(&mut self, &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __move_entity(&mut self, other: &mut Self, from: &IndexedEntity<Self>, to: &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __dynamic(&self) -> Option<&DynamicComponents<Self>>
+    {
+        None
+    }
+    #[doc(hidden)]
+    fn __dynamic_mut(&mut self) -> Option<&mut DynamicComponents<Self>>
+    {
+        None
+    }
+}
+
+/// Component managers whose entities can be copied with `DataHelper::clone_entity`.
+///
+/// Implemented by `components!` whenever every component type is `Clone`.
+pub trait CloneComponents: ComponentManager
+{
+    #[doc(hidden)]
+    fn __clone_entity(&mut self, from: &IndexedEntity<Self>, to: &IndexedEntity<Self>);
+    #[doc(hidden)]
+    fn __restore(&mut self, snapshot: &Self);
 }
 
 pub trait ServiceManager: 'static {}
@@ -50,6 +262,65 @@ pub trait SystemManager
     fn __update(&mut self, &mut DataHelper<Self::Components, Self::Services>);
 }
 
+/// Whatever the entity manager tells about entities being built and removed.
+///
+/// Implemented for system managers, observers and entity groups, and for tuples of four of them
+/// so that a world's groups, static systems, dynamic systems and observers are told together.
+#[doc(hidden)]
+pub trait Listener<C: ComponentManager, M: ServiceManager>
+{
+    fn __activate(&mut self, entity: EntityData<C>, components: &C, services: &mut M);
+    fn __reactivate(&mut self, entity: EntityData<C>, components: &C, services: &mut M);
+    fn __deactivate(&mut self, entity: EntityData<C>, components: &C, services: &mut M);
+}
+
+impl<S: SystemManager> Listener<S::Components, S::Services> for S
+{
+    fn __activate(&mut self, en: EntityData<S::Components>, co: &S::Components, se: &mut S::Services)
+    {
+        self.__activated(en, co, se);
+    }
+
+    fn __reactivate(&mut self, en: EntityData<S::Components>, co: &S::Components, se: &mut S::Services)
+    {
+        self.__reactivated(en, co, se);
+    }
+
+    fn __deactivate(&mut self, en: EntityData<S::Components>, co: &S::Components, se: &mut S::Services)
+    {
+        self.__deactivated(en, co, se);
+    }
+}
+
+impl<'a, C, M, G, A, B, O> Listener<C, M> for (&'a mut G, &'a mut A, &'a mut B, &'a mut O)
+    where C: ComponentManager, M: ServiceManager,
+          G: Listener<C, M>, A: Listener<C, M>, B: Listener<C, M>, O: Listener<C, M>
+{
+    fn __activate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        self.0.__activate(en, co, se);
+        self.1.__activate(en, co, se);
+        self.2.__activate(en, co, se);
+        self.3.__activate(en, co, se);
+    }
+
+    fn __reactivate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        self.0.__reactivate(en, co, se);
+        self.1.__reactivate(en, co, se);
+        self.2.__reactivate(en, co, se);
+        self.3.__reactivate(en, co, se);
+    }
+
+    fn __deactivate(&mut self, en: EntityData<C>, co: &C, se: &mut M)
+    {
+        self.0.__deactivate(en, co, se);
+        self.1.__deactivate(en, co, se);
+        self.2.__deactivate(en, co, se);
+        self.3.__deactivate(en, co, se);
+    }
+}
+
 impl<S: SystemManager> Deref for World<S>
 {
     type Target = DataHelper<S::Components, S::Services>;
@@ -96,15 +367,200 @@ impl<C: ComponentManager, M: ServiceManager> DataHelper<C, M>
         }
     }
 
+    /// Like `World::modify_entity`, except that systems hear about the change on the next flush,
+    /// since they can't be reached from here.
+    ///
+    /// Returns `None` if the entity doesn't exist.
+    pub fn modify_entity<F, R>(&mut self, entity: &Entity, call: F) -> Option<R>
+        where F: FnOnce(ModifyData<C>, &mut C) -> R
+    {
+        if self.entities.is_valid(entity) {
+            let ret = call(ModifyData(&self.entities.indexed(entity).__clone()), self);
+            self.entities.queue_reactivate(*entity);
+            Some(ret)
+        } else {
+            None
+        }
+    }
+
     pub fn create_entity<B>(&mut self, builder: B) -> Entity where B: EntityBuilder<C>
     {
         self.entities.create_entity(builder, &mut self.components)
     }
 
+    /// Creates a new entity with a copy of each of `entity`'s components, including those in
+    /// dynamic lists. Like any other new entity, systems hear about it on the next flush.
+    ///
+    /// Panics if `entity` doesn't exist.
+    pub fn clone_entity(&mut self, entity: &Entity) -> Entity where C: CloneComponents
+    {
+        let from = self.entities.indexed(entity).__clone();
+        self.entities.create_entity(|e: BuildData<C>, c: &mut C| {
+            c.__clone_entity(&from, e.0);
+            if let Some(dynamic) = c.__dynamic_mut() {
+                dynamic.__clone_entity(&from, e.0);
+            }
+        }, &mut self.components)
+    }
+
+    pub fn entities(&self) -> EntityIter<'_, C>
+    {
+        self.entities.iter()
+    }
+
     pub fn remove_entity(&mut self, entity: Entity)
     {
         self.entities.remove_entity(entity);
     }
+
+    /// Whether entities are iterated in order of id, as needed to replay recordings.
+    pub fn is_deterministic(&self) -> bool
+    {
+        self.entities.is_sorted()
+    }
+
+    /// Iterates entities in order of id everywhere, so that the same commands applied to the
+    /// same state always give the same result. Off by default, since each iteration then sorts
+    /// the entities first.
+    pub fn set_deterministic(&mut self, deterministic: bool)
+    {
+        self.entities.set_sorted(deterministic);
+    }
+
+    /// The group added as `name` with `World::add_entity_group`.
+    pub fn entity_group(&self, name: &str) -> Option<Arc<EntityGroup<C>>>
+    {
+        self.groups.get(name)
+    }
+
+    /// The index of entity names and tags.
+    pub fn names(&self) -> &Names
+    {
+        self.entities.names()
+    }
+
+    /// Names an entity, taking the name from any entity that already had it.
+    ///
+    /// Returns the entity that lost the name. Does nothing if `entity` doesn't exist.
+    pub fn set_name(&mut self, entity: &Entity, name: &str) -> Option<Entity>
+    {
+        if self.entities.is_valid(entity) {
+            self.entities.names_mut().set_name(*entity, name)
+        } else {
+            None
+        }
+    }
+
+    pub fn remove_name(&mut self, entity: &Entity) -> Option<String>
+    {
+        self.entities.names_mut().remove_name(entity)
+    }
+
+    /// Tags an entity, returning the interned tag, or `None` if `entity` doesn't exist.
+    pub fn add_tag(&mut self, entity: &Entity, tag: &str) -> Option<Tag>
+    {
+        if self.entities.is_valid(entity) {
+            Some(self.entities.names_mut().add_tag(*entity, tag))
+        } else {
+            None
+        }
+    }
+
+    /// Removes a tag from an entity, returning whether it had it.
+    pub fn remove_tag(&mut self, entity: &Entity, tag: &str) -> bool
+    {
+        match self.entities.names().tag(tag) {
+            Some(tag) => self.entities.names_mut().remove_tag(entity, tag),
+            None => false,
+        }
+    }
+
+    /// The list of `T` components, for code that's generic over the component manager.
+    pub fn list<T: Component>(&self) -> &ComponentList<C, T> where C: HasComponent<T>
+    {
+        self.components.list()
+    }
+
+    pub fn list_mut<T: Component>(&mut self) -> &mut ComponentList<C, T> where C: HasComponent<T>
+    {
+        self.components.list_mut()
+    }
+
+    /// The component lists registered at runtime, if the component manager has a place for them.
+    pub fn dynamic(&self) -> Option<&DynamicComponents<C>>
+    {
+        self.components.__dynamic()
+    }
+
+    pub fn dynamic_mut(&mut self) -> Option<&mut DynamicComponents<C>>
+    {
+        self.components.__dynamic_mut()
+    }
+
+    /// One of the component manager's component groups.
+    pub fn group<G>(&self) -> &G where C: HasGroup<G>
+    {
+        self.components.group()
+    }
+
+    pub fn group_mut<G>(&mut self) -> &mut G where C: HasGroup<G>
+    {
+        self.components.group_mut()
+    }
+
+    /// One of the service manager's services.
+    pub fn service<T>(&self) -> &T where M: HasService<T>
+    {
+        self.services.service()
+    }
+
+    pub fn service_mut<T>(&mut self) -> &mut T where M: HasService<T>
+    {
+        self.services.service_mut()
+    }
+
+    /// Computes the changes needed to get from `old` to the current state.
+    pub fn diff(&self, old: &DataHelper<C, M>) -> Delta<C> where C: DiffComponents
+    {
+        diff(&old.components, &old.entities, &self.components, &self.entities)
+    }
+
+    /// Computes the changes made since `snapshot` was taken.
+    pub fn changes_since(&self, snapshot: &Snapshot<C, M>) -> Delta<C> where C: DiffComponents
+    {
+        diff(&snapshot.components, &snapshot.entities, &self.components, &self.entities)
+    }
+
+    /// Copies the current state, including entities still waiting in the queue.
+    pub fn snapshot(&self) -> Snapshot<C, M> where C: Clone, M: Clone
+    {
+        Snapshot {
+            components: self.components.clone(),
+            services: self.services.clone(),
+            entities: self.entities.clone(),
+        }
+    }
+}
+
+fn diff<C>(old: &C, old_entities: &EntityManager<C>, new: &C, new_entities: &EntityManager<C>) -> Delta<C>
+    where C: DiffComponents
+{
+    let mut created = Vec::new();
+    let mut pairs = Vec::new();
+    for e in new_entities.iter() {
+        let old_index = if old_entities.is_valid(&e) {
+            Some(old_entities.indexed(&e).index())
+        } else {
+            created.push(**e);
+            None
+        };
+        pairs.push((**e, old_index, e.index()));
+    }
+    Delta {
+        created,
+        removed: old_entities.iter().map(|e| **e).filter(|e| !new_entities.is_valid(e)).collect(),
+        components: new.__diff(old, &pairs),
+    }
 }
 
 #[cfg(feature="serialisation")]
@@ -122,6 +578,8 @@ unsafe impl<C: ComponentManager, M: ServiceManager> CerealData for DataHelper<C,
         Ok(DataHelper {
             components: components,
             services: services,
+            resources: Resources::new(),
+            groups: EntityGroups::new(),
             entities: entities,
         })
     }
@@ -132,14 +590,23 @@ impl<S: SystemManager> World<S> where DataHelper<S::Components, S::Services>: Ce
     pub fn load(reader: &mut Read) -> CerealResult<World<S>> {
         let mut world = World {
             systems: S::__new(),
+            dynamic_systems: DynamicSystems::new(),
+            observers: Observers::new(),
             data: try!(CerealData::read(reader)),
         };
-        world.refresh();
+        world.data.entities.activate_all(
+            &world.data.components,
+            &mut world.data.services,
+            &mut world.systems
+        );
         Ok(world)
     }
 
-    pub fn save(&mut self, writer: &mut Write) -> CerealResult<()> {
-        self.flush_queue();
+    /// Writes the world's data, including any entities still waiting to be built or removed.
+    ///
+    /// The pending events are restored by `load` and processed on the next flush, so it's safe
+    /// to save at any point. From inside a `Process`, write the `DataHelper` directly instead.
+    pub fn save(&self, writer: &mut Write) -> CerealResult<()> {
         self.data.write(writer)
     }
 }
@@ -150,9 +617,13 @@ impl<S: SystemManager> World<S>
     {
         World {
             systems: S::__new(),
+            dynamic_systems: DynamicSystems::new(),
+            observers: Observers::new(),
             data: DataHelper {
                 components: S::Components::__new(),
                 services: S::Services::default(),
+                resources: Resources::new(),
+                groups: EntityGroups::new(),
                 entities: EntityManager::new(),
             },
         }
@@ -162,9 +633,13 @@ impl<S: SystemManager> World<S>
     {
         World {
             systems: S::__new(),
+            dynamic_systems: DynamicSystems::new(),
+            observers: Observers::new(),
             data: DataHelper {
                 components: S::Components::__new(),
                 services: services,
+                resources: Resources::new(),
+                groups: EntityGroups::new(),
                 entities: EntityManager::new(),
             },
         }
@@ -179,16 +654,87 @@ impl<S: SystemManager> World<S>
     {
         let indexed = self.data.entities.indexed(&entity);
         modifier.modify(ModifyData(indexed), &mut self.data.components);
-        self.systems.__reactivated(
+        (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers).__reactivate(
             EntityData(indexed), &self.data.components, &mut self.data.services
         );
     }
 
+    /// Replaces the world's data with a copy of `snapshot`.
+    ///
+    /// Systems are told about the change by deactivating every current entity and then
+    /// activating every entity in the snapshot, so their state stays consistent. Component lists
+    /// keep the hooks they have now, rather than the ones they had when the snapshot was taken.
+    pub fn restore(&mut self, snapshot: &Snapshot<S::Components, S::Services>)
+        where S::Components: CloneComponents, S::Services: Clone
+    {
+        self.data.entities.deactivate_all(&self.data.components, &mut self.data.services,
+            &mut (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers));
+        self.data.components.__restore(&snapshot.components);
+        if let (Some(dynamic), Some(old)) = (self.data.components.__dynamic_mut(), snapshot.components.__dynamic()) {
+            dynamic.__restore(old);
+        }
+        self.data.services.clone_from(&snapshot.services);
+        let sorted = self.data.entities.is_sorted();
+        self.data.entities = snapshot.entities.clone();
+        self.data.entities.set_sorted(sorted);
+        self.data.entities.activate_all(&self.data.components, &mut self.data.services,
+            &mut (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers));
+    }
+
+    /// Applies changes computed by `DataHelper::diff` or `DataHelper::changes_since`.
+    ///
+    /// Created and removed entities go through the queue as usual, while entities whose
+    /// components changed are reactivated immediately. Created entities that already exist are
+    /// treated as changed, so applying the same delta twice doesn't build them again.
+    pub fn apply_delta(&mut self, delta: &Delta<S::Components>) where S::Components: DiffComponents
+    {
+        for entity in &delta.removed {
+            if self.data.entities.is_valid(entity) {
+                self.data.entities.remove_entity(*entity);
+            }
+        }
+        // Entities that already exist, such as when a delta is applied twice, are only updated.
+        let created: Vec<Entity> = delta.created.iter()
+            .filter(|&&entity| self.data.entities.create_with_id(entity))
+            .cloned()
+            .collect();
+
+        let mut touched = Vec::new();
+        {
+            let entities = &self.data.entities;
+            self.data.components.__apply(&delta.components, &mut |entity| {
+                if entities.is_valid(entity) {
+                    touched.push(*entity);
+                    Some(entities.indexed(entity).index())
+                } else {
+                    None
+                }
+            });
+        }
+
+        for &entity in &created {
+            self.data.entities.queue_build(entity);
+        }
+        touched.sort_by_key(|e| e.id());
+        touched.dedup();
+        for entity in touched {
+            if !created.contains(&entity) {
+                (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers).__reactivate(
+                    EntityData(self.data.entities.indexed(&entity)),
+                    &self.data.components,
+                    &mut self.data.services
+                );
+            }
+        }
+    }
+
     pub fn refresh(&mut self)
     {
         self.flush_queue();
         for entity in self.data.entities.iter() {
-            self.systems.__reactivated(entity, &self.data.components, &mut self.data.services);
+            (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers).__reactivate(
+                entity, &self.data.components, &mut self.data.services
+            );
         }
     }
 
@@ -197,7 +743,7 @@ impl<S: SystemManager> World<S>
         self.data.entities.flush_queue(
             &mut self.data.components,
             &mut self.data.services,
-            &mut self.systems
+            &mut (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers)
         );
     }
 
@@ -205,6 +751,179 @@ impl<S: SystemManager> World<S>
     {
         self.flush_queue();
         self.systems.__update(&mut self.data);
+        self.dynamic_systems.__update(&mut self.data);
+        self.flush_queue();
+    }
+
+    /// Moves every entity from `other` into this world, giving each a new identifier.
+    ///
+    /// Both worlds are flushed first, so entities waiting to be removed from `other` are left
+    /// behind. The moved entities are then activated in this world's systems, while `other`'s
+    /// services and systems are dropped. Returns the identifier each entity was given.
+    ///
+    /// Names and tags come across with their entities. A name already used in this world is
+    /// taken from the entity that had it, as with `set_name`.
+    ///
+    /// Components that refer to other entities keep the old identifiers; use `merge_with` to
+    /// update them.
+    pub fn merge(&mut self, other: World<S>) -> EntityMap
+    {
+        self.merge_with(other, |_, _, _| {})
+    }
+
+    /// Like `merge`, but calls `remap` on each moved entity before it's activated, such as
+    /// `|map, e, c| c.target.map_entities(&e, map)`.
+    pub fn merge_with<F>(&mut self, mut other: World<S>, mut remap: F) -> EntityMap
+        where F: FnMut(&EntityMap, EntityData<S::Components>, &mut S::Components)
+    {
+        other.flush_queue();
+        self.flush_queue();
+        let mut map = EntityMap::new();
+        for entity in other.data.entities.iter() {
+            let new = self.data.entities.create();
+            let to = self.data.entities.indexed(&new);
+            self.data.components.__move_entity(&mut other.data.components, &entity, to);
+            if let (Some(dynamic), Some(other)) = (self.data.components.__dynamic_mut(), other.data.components.__dynamic_mut()) {
+                dynamic.__move_entity(other, &entity, to);
+            }
+            map.insert(**entity, new);
+        }
+        for (old, new) in map.iter() {
+            self.data.entities.names_mut().move_entity(other.data.entities.names_mut(), old, *new);
+        }
+        for (_, new) in map.iter() {
+            self.data.with_entity_data(new, |e, c| remap(&map, e, c));
+            self.data.entities.queue_build(*new);
+        }
+        self.flush_queue();
+        map
+    }
+
+    /// Takes an entity and all of its components out of the world, so that it can be put into
+    /// another with `insert_entity`. Returns `None` if the entity doesn't exist.
+    ///
+    /// The queue is flushed first, and the entity is then deactivated in this world's systems.
+    pub fn take_entity(&mut self, entity: Entity) -> Option<EntityBundle<S::Components>>
+    {
+        self.flush_queue();
+        if !self.data.entities.is_valid(&entity) {
+            return None;
+        }
+        let mut bundle: EntityBundle<S::Components> = EntityBundle::new(entity);
+        {
+            let from = self.data.entities.indexed(&entity);
+            let to = bundle.entities.indexed(&bundle.local);
+            (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers).__deactivate(
+                EntityData(from),
+                &self.data.components,
+                &mut self.data.services
+            );
+            bundle.components.__move_entity(&mut self.data.components, from, to);
+            if let (Some(dynamic), Some(other)) = (bundle.components.__dynamic_mut(), self.data.components.__dynamic_mut()) {
+                dynamic.__move_entity(other, from, to);
+            }
+        }
+        bundle.entities.names_mut().move_entity(self.data.entities.names_mut(), &entity, bundle.local);
+        self.data.entities.remove(&entity);
+        Some(bundle)
+    }
+
+    /// Puts an entity taken with `take_entity` into this world under a new identifier, which is
+    /// returned. The entity is activated straight away, while anything else waiting in the queue
+    /// is left for the next flush.
+    ///
+    /// Components that refer to other entities keep the identifiers from the old world.
+    pub fn insert_entity(&mut self, mut bundle: EntityBundle<S::Components>) -> Entity
+    {
+        let entity = self.data.entities.create();
+        {
+            let from = bundle.entities.indexed(&bundle.local);
+            let to = self.data.entities.indexed(&entity);
+            self.data.components.__move_entity(&mut bundle.components, from, to);
+            if let (Some(dynamic), Some(other)) = (self.data.components.__dynamic_mut(), bundle.components.__dynamic_mut()) {
+                dynamic.__move_entity(other, from, to);
+            }
+        }
+        self.data.entities.names_mut().move_entity(bundle.entities.names_mut(), &bundle.local, entity);
+        (&mut self.data.groups, &mut self.systems, &mut self.dynamic_systems, &mut self.observers).__activate(
+            EntityData(self.data.entities.indexed(&entity)), &self.data.components, &mut self.data.services
+        );
+        entity
+    }
+
+    /// Adds an observer which runs `callback` whenever `trigger` happens to an entity matching
+    /// `aspect`.
+    ///
+    /// The queue is flushed first, and entities which already match are recorded without running
+    /// the callback, so they aren't reported as entering later on. The callback has to be `Send`
+    /// so that the world still is.
+    pub fn add_observer<F>(&mut self, trigger: Trigger, aspect: Aspect<S::Components>, callback: F) -> ObserverId
+        where F: FnMut(EntityData<S::Components>, &S::Components, &mut S::Services) + Send + 'static
+    {
+        self.flush_queue();
+        let id = self.observers.add(trigger, aspect, callback);
+        for entity in self.data.entities.iter() {
+            self.observers.__track(id, &entity, &self.data.components);
+        }
+        id
+    }
+
+    /// Removes an observer, returning whether it existed.
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool
+    {
+        self.observers.remove(id)
+    }
+
+    /// Adds a named group of the entities matching `aspect`, returning the aspect back if the
+    /// name is taken. The queue is flushed first, so the group starts out with every entity that
+    /// matches.
+    pub fn add_entity_group(&mut self, name: &str, aspect: Aspect<S::Components>) -> Result<(), Aspect<S::Components>>
+    {
+        if self.data.groups.contains(name) {
+            return Err(aspect);
+        }
+        self.flush_queue();
+        self.data.groups.__add(name, aspect, self.data.entities.iter(), &self.data.components)
+    }
+
+    /// Removes a named group, returning whether it existed.
+    pub fn remove_entity_group(&mut self, name: &str) -> bool
+    {
+        self.data.groups.remove(name)
+    }
+
+    /// Adds a system which is updated after the others, returning it back if the name is taken.
+    ///
+    /// The queue is flushed first, and the system is then activated for every entity. The system
+    /// has to be `Send` so that the world still is.
+    pub fn add_system<P>(&mut self, name: &str, system: P) -> Result<(), BoxedProcess<S::Components, S::Services>>
+        where P: Process<Components=S::Components, Services=S::Services> + Send + 'static
+    {
+        if self.dynamic_systems.contains(name) {
+            return Err(Box::new(system));
+        }
         self.flush_queue();
+        let mut system = Box::new(system);
+        for entity in self.data.entities.iter() {
+            system.activated(&entity, &self.data.components, &mut self.data.services);
+        }
+        self.dynamic_systems.push(name, system)
+    }
+
+    /// Removes a system added with `add_system`, after flushing the queue and deactivating the
+    /// system for every entity.
+    pub fn remove_system(&mut self, name: &str) -> Option<BoxedProcess<S::Components, S::Services>>
+    {
+        if !self.dynamic_systems.contains(name) {
+            return None;
+        }
+        self.flush_queue();
+        let mut system = self.dynamic_systems.remove(name);
+        if let Some(ref mut system) = system {
+            for entity in self.data.entities.iter() {
+                system.deactivated(&entity, &self.data.components, &mut self.data.services);
+            }
+        }
+        system
     }
 }
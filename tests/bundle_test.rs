@@ -0,0 +1,109 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, ComponentList, EntityData, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Loot(pub u32);
+
+components! {
+    #[dynamic(extra)]
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            tracker: EntitySystem<Tracker> = EntitySystem::new(Tracker(0, 0), aspect!(<TestComponents> all: [position])),
+        }
+    }
+}
+
+/// Counts activations and deactivations.
+pub struct Tracker(pub usize, pub usize);
+impl System for Tracker
+{
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.0 += 1;
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.1 += 1;
+    }
+}
+impl EntityProcess for Tracker
+{
+    fn process(&mut self, _: ecs::EntityIter<TestComponents>, _: &mut ecs::DataHelper<TestComponents, ()>) {}
+}
+
+#[test]
+fn test_take_and_insert_entity()
+{
+    let mut source = World::<TestSystems>::new();
+    source.extra.register("loot", ComponentList::<TestComponents, Loot>::cold());
+    let stay = source.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+    let player = source.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(7));
+        c.extra.list_mut::<Loot>().unwrap().add(&e, Loot(3));
+    });
+
+    source.set_name(&player, "player");
+    source.add_tag(&player, "hero");
+    source.add_tag(&stay, "hero");
+
+    let mut bundle = source.take_entity(player).unwrap();
+    assert!(source.take_entity(player).is_none());
+    assert_eq!(bundle.entity(), player);
+    assert_eq!(bundle.with_entity_data(|e, c| c.position[e]), Position(7));
+    assert_eq!(bundle.name(), Some("player"));
+    assert_eq!(bundle.tags().collect::<Vec<_>>(), vec!["hero"]);
+    assert_eq!(source.names().entity("player"), None);
+    let hero = source.names().tag("hero").unwrap();
+    assert_eq!(source.names().tagged(hero).collect::<Vec<_>>(), vec![stay]);
+
+    // The source activated both entities, then deactivated the one that left.
+    assert_eq!((source.systems.tracker.0, source.systems.tracker.1), (2, 1));
+    assert_eq!(source.entities().count(), 1);
+    assert_eq!(source.with_entity_data(&player, |_, _| ()), None);
+    assert_eq!(source.with_entity_data(&stay, |e, c| c.position[e]), Some(Position(0)));
+
+    // Only the inserted entity is activated; anything else queued waits for the next flush.
+    let mut target = World::<TestSystems>::new();
+    let pending = target.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(1));
+    });
+    let moved = target.insert_entity(bundle);
+    assert_eq!((target.systems.tracker.0, target.systems.tracker.1), (1, 0));
+    target.flush_queue();
+    assert_eq!((target.systems.tracker.0, target.systems.tracker.1), (2, 0));
+    assert_eq!(target.with_entity_data(&moved, |e, c| c.position[e]), Some(Position(7)));
+    assert_eq!(target.extra.names(), vec!["loot"]);
+    assert_eq!(target.with_entity_data(&moved, |e, c| c.extra.list::<Loot>().unwrap().get(&e)), Some(Some(Loot(3))));
+    assert_eq!(target.names().entity("player"), Some(moved));
+    let hero = target.names().tag("hero").unwrap();
+    assert!(target.names().has_tag(&moved, hero));
+
+    // Entities can go back and forth.
+    let bundle = target.take_entity(moved).unwrap();
+    let back = source.insert_entity(bundle);
+    assert_eq!(source.entities().count(), 2);
+    assert_eq!(source.with_entity_data(&back, |e, c| c.position[e]), Some(Position(7)));
+    assert_eq!(source.names().entity("player"), Some(back));
+    let hero = source.names().tag("hero").unwrap();
+    assert_eq!(source.names().tagged(hero).collect::<Vec<_>>(), vec![stay, back]);
+    assert_eq!(target.entities().count(), 1);
+    assert_eq!(target.with_entity_data(&pending, |e, c| c.position[e]), Some(Position(1)));
+}
@@ -0,0 +1,78 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, ComponentList, EntityData, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inventory(pub Vec<&'static str>);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub u32);
+
+components! {
+    #[dynamic(extra)]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] inventory: Inventory,
+        #[cold] tag: (),
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            tracker: EntitySystem<Tracker> = EntitySystem::new(Tracker(0), aspect!(<TestComponents> all: [position])),
+        }
+    }
+}
+
+pub struct Tracker(pub usize);
+impl System for Tracker
+{
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.0 += 1;
+    }
+}
+impl EntityProcess for Tracker
+{
+    fn process(&mut self, _: ecs::EntityIter<TestComponents>, _: &mut ecs::DataHelper<TestComponents, ()>) {}
+}
+
+#[test]
+fn test_clone_entity()
+{
+    let mut world = World::<TestSystems>::new();
+    world.extra.register("health", ComponentList::<TestComponents, Health>::hot());
+    let original = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(3));
+        c.inventory.add(&e, Inventory(vec!["sword"]));
+        c.extra.list_mut::<Health>().unwrap().add(&e, Health(7));
+    });
+    world.flush_queue();
+    assert_eq!(world.systems.tracker.0, 1);
+
+    let copy = world.clone_entity(&original);
+    assert!(copy != original);
+    // The copy waits in the queue like any other new entity.
+    assert_eq!(world.systems.tracker.0, 1);
+    world.flush_queue();
+    assert_eq!(world.systems.tracker.0, 2);
+
+    assert_eq!(world.with_entity_data(&copy, |e, c| (c.position.get(&e), c.inventory.get(&e), c.tag.has(&e))),
+        Some((Some(Position(3)), Some(Inventory(vec!["sword"])), false)));
+    assert_eq!(world.with_entity_data(&copy, |e, c| c.extra.list::<Health>().unwrap().get(&e)), Some(Some(Health(7))));
+
+    // The copy is independent of the original.
+    world.with_entity_data(&copy, |e, c| c.inventory[e].0.push("shield"));
+    assert_eq!(world.with_entity_data(&original, |e, c| c.inventory[e].0.len()), Some(1));
+}
@@ -0,0 +1,122 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, EntityData, ModifyData, System, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Team(pub u8);
+
+components! {
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] team: Team,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            tracker: Tracker = Tracker(0, 0),
+        }
+    }
+}
+
+/// Counts active entities and reactivations.
+pub struct Tracker(pub usize, pub usize);
+impl System for Tracker {
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.0 += 1;
+    }
+    fn reactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.1 += 1;
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.0 -= 1;
+    }
+}
+
+fn spawn(world: &mut World<TestSystems>, x: i32, team: Option<u8>) -> ecs::Entity {
+    world.create_entity(move |e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(x));
+        if let Some(team) = team {
+            c.team.add(&e, Team(team));
+        }
+    })
+}
+
+#[test]
+fn test_delta_roundtrip() {
+    let mut server = World::<TestSystems>::new();
+    let moved = spawn(&mut server, 1, Some(1));
+    let removed = spawn(&mut server, 2, None);
+    let untouched = spawn(&mut server, 3, Some(3));
+    server.flush_queue();
+
+    let before = server.snapshot();
+    let mut client = World::<TestSystems>::new();
+    client.restore(&before);
+
+    server.modify_entity(moved, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position[e] = Position(10);
+        c.team.remove(&e);
+    });
+    server.remove_entity(removed);
+    let created = spawn(&mut server, 4, Some(4));
+    server.flush_queue();
+
+    let delta = server.changes_since(&before);
+    assert_eq!(delta.created, vec![created]);
+    assert_eq!(delta.removed, vec![removed]);
+    {
+        let (ref positions, ref teams) = delta.components;
+        assert_eq!(positions.added, vec![(created, Position(4))]);
+        assert_eq!(positions.changed, vec![(moved, Position(10))]);
+        assert_eq!(teams.removed, vec![moved]);
+    }
+    assert!(server.diff(&server).components.0.is_empty());
+
+    client.apply_delta(&delta);
+    client.flush_queue();
+
+    assert_eq!(client.systems.tracker.0, 3);
+    assert_eq!(client.systems.tracker.1, 1);
+    for &entity in &[moved, untouched, created] {
+        let expected = server.with_entity_data(&entity, |e, c| (c.position.get(&e), c.team.get(&e)));
+        let actual = client.with_entity_data(&entity, |e, c| (c.position.get(&e), c.team.get(&e)));
+        assert_eq!(expected, actual);
+    }
+    assert!(client.with_entity_data(&removed, |_, _| ()).is_none());
+
+    // Ids keep lining up after the delta.
+    assert_eq!(spawn(&mut server, 0, None), spawn(&mut client, 0, None));
+}
+
+#[test]
+fn test_delta_applied_twice() {
+    let mut server = World::<TestSystems>::new();
+    let before = server.snapshot();
+    let created = spawn(&mut server, 1, Some(1));
+    server.flush_queue();
+    let delta = server.changes_since(&before);
+
+    let mut client = World::<TestSystems>::new();
+    client.apply_delta(&delta);
+    client.flush_queue();
+    assert_eq!(client.systems.tracker.0, 1);
+
+    // The second time, the entity already exists and is only reactivated.
+    client.apply_delta(&delta);
+    client.flush_queue();
+    assert_eq!(client.systems.tracker.0, 1);
+    assert_eq!(client.systems.tracker.1, 1);
+    assert_eq!(client.with_entity_data(&created, |e, c| c.position[e]), Some(Position(1)));
+}
@@ -0,0 +1,111 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, DataHelper, EntityData, EntityIter, Process, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+#[derive(Default)]
+pub struct TestServices
+{
+    pub log: Vec<&'static str>,
+}
+
+impl ecs::ServiceManager for TestServices {}
+
+systems! {
+    struct TestSystems<TestComponents, TestServices> {
+        active: {
+            first: Logger = Logger("static"),
+        },
+        passive: {
+        }
+    }
+}
+
+pub struct Logger(&'static str);
+impl System for Logger { type Components = TestComponents; type Services = TestServices; }
+impl Process for Logger
+{
+    fn process(&mut self, data: &mut DataHelper<TestComponents, TestServices>)
+    {
+        data.services.log.push(self.0);
+    }
+}
+
+/// Moves entities along, and logs when it gains or loses one.
+pub struct Motion;
+impl System for Motion
+{
+    type Components = TestComponents;
+    type Services = TestServices;
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, services: &mut TestServices)
+    {
+        services.log.push("activated");
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, services: &mut TestServices)
+    {
+        services.log.push("deactivated");
+    }
+}
+impl EntityProcess for Motion
+{
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, TestServices>)
+    {
+        for e in entities {
+            data.position[e].0 += 1;
+        }
+        data.services.log.push("motion");
+    }
+}
+
+#[test]
+fn test_dynamic_systems()
+{
+    let mut world = World::<TestSystems>::new();
+    let entity = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+
+    // Existing entities are activated in the new system straight away.
+    assert!(world.add_system("motion", EntitySystem::new(Motion, aspect!(<TestComponents> all: [position]))).is_ok());
+    assert!(world.add_system("motion", Logger("duplicate")).is_err());
+    assert!(world.add_system("late", Logger("dynamic")).is_ok());
+    assert_eq!(world.dynamic_systems.names(), vec!["motion", "late"]);
+    assert_eq!(world.services.log, vec!["activated"]);
+
+    // Dynamic systems run after the static ones, in the order they were added.
+    world.update();
+    assert_eq!(world.services.log, vec!["activated", "static", "motion", "dynamic"]);
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.position[e]), Some(Position(1)));
+
+    // They hear about entities like any other system.
+    world.services.log.clear();
+    world.remove_entity(entity);
+    world.update();
+    assert_eq!(world.services.log, vec!["deactivated", "static", "motion", "dynamic"]);
+
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+    world.services.log.clear();
+    let motion = world.remove_system("motion").unwrap();
+    assert!(world.remove_system("motion").is_none());
+    world.update();
+    assert_eq!(world.services.log, vec!["activated", "deactivated", "static", "dynamic"]);
+
+    // A removed system can be added again, and is activated for the entities it missed.
+    world.services.log.clear();
+    assert!(world.add_system("motion", motion).is_ok());
+    world.update();
+    assert_eq!(world.services.log, vec!["activated", "static", "dynamic", "motion"]);
+}
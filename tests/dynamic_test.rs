@@ -0,0 +1,114 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Aspect, BuildData, ComponentList, DataHelper, EntityData, EntityIter, ModifyData, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+/// Only known to the "mod" that registers it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub i32);
+
+components! {
+    #[dynamic(extra)]
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+            poison: EntitySystem<Poison> = EntitySystem::new(Poison,
+                aspect!(<TestComponents> all: [position]).with_dynamic::<Health>()),
+        },
+        passive: {
+            named: EntitySystem<Tracker> = EntitySystem::new(Tracker(0), Aspect::all().with_named("health")),
+            unnamed: EntitySystem<Tracker> = EntitySystem::new(Tracker(0), Aspect::all().without_named("health")),
+        }
+    }
+}
+
+pub struct Poison;
+impl System for Poison { type Components = TestComponents; type Services = (); }
+impl EntityProcess for Poison
+{
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, ()>)
+    {
+        let health = data.dynamic_mut().and_then(|d| d.list_mut::<Health>()).unwrap();
+        for e in entities {
+            health[e].0 -= 1;
+        }
+    }
+}
+
+/// Counts the entities it's interested in.
+pub struct Tracker(pub usize);
+impl System for Tracker
+{
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.0 += 1;
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.0 -= 1;
+    }
+}
+impl EntityProcess for Tracker
+{
+    fn process(&mut self, _: EntityIter<TestComponents>, _: &mut DataHelper<TestComponents, ()>) {}
+}
+
+#[test]
+fn test_dynamic_components()
+{
+    let mut world = World::<TestSystems>::new();
+    assert!(world.extra.register("health", ComponentList::<TestComponents, Health>::cold()));
+    assert!(!world.extra.register("health", ComponentList::<TestComponents, Health>::hot()));
+    assert_eq!(world.extra.names(), vec!["health"]);
+
+    let hero = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.extra.list_mut::<Health>().unwrap().add(&e, Health(10));
+    });
+    let rock = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(5));
+    });
+    world.update();
+    world.update();
+
+    assert_eq!(world.with_entity_data(&hero, |e, c| c.extra.list::<Health>().unwrap().get(&e)), Some(Some(Health(8))));
+    assert_eq!(world.with_entity_data(&rock, |e, c| c.extra.has_named("health", &e)), Some(false));
+    assert_eq!(world.systems.named.0, 1);
+    assert_eq!(world.systems.unnamed.0, 1);
+
+    // Snapshots copy the registered lists.
+    let snapshot = world.snapshot();
+    world.modify_entity(hero, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        assert!(c.extra.remove_named("health", &e));
+    });
+    world.update();
+    assert_eq!(world.systems.named.0, 0);
+    world.restore(&snapshot);
+    assert_eq!(world.with_entity_data(&hero, |e, c| c.extra.list::<Health>().unwrap().get(&e)), Some(Some(Health(8))));
+
+    // Removing the entity clears its dynamic components too.
+    let index = world.with_entity_data(&hero, |e, _| e.index()).unwrap();
+    world.remove_entity(hero);
+    world.flush_queue();
+    assert!(world.extra.list::<Health>().unwrap().__get(index).is_none());
+}
+
+#[test]
+fn test_dynamic_components_are_send_and_sync()
+{
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<DataHelper<TestComponents, ()>>();
+}
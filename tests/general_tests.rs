@@ -3,6 +3,7 @@
 extern crate ecs;
 
 use ecs::{ModifyData};
+use ecs::Change::{Remove, Set};
 use ecs::{World};
 use ecs::{Process, System};
 use ecs::system::{EntityProcess, EntitySystem};
@@ -23,6 +24,7 @@ pub struct SomeFeature;
 
 components! {
     #[builder(EntityInit)]
+    #[modifier(EntityChange)]
     struct TestComponents {
         #[hot] blank_data: (),
         #[hot] position: Position,
@@ -114,11 +116,13 @@ fn test_general_1()
         assert!(c.feature.insert(&e, SomeFeature).is_some());
     });
 
-    // world.modify_entity(entity, EntityChange {
-    //     position: Some(Some(Position { x: 0.5, y: 0.7 })), // Set
-    //     team: Some(None), // Remove
-    //     ..Default::default() // Ignore the rest (None)
-    // });
+    world.modify_entity(entity, EntityChange {
+        position: Set(Position { x: 0.5, y: 0.7 }),
+        team: Remove,
+        ..Default::default() // Leave the rest unchanged
+    });
+    assert_eq!(world.with_entity_data(&entity, |e, c| (c.position.get(&e), c.team.has(&e), c.feature.has(&e))),
+        Some((Some(Position { x: 0.5, y: 0.7 }), false, true)));
 
     process!(world, print_position);
 
@@ -141,3 +145,22 @@ fn test_general_1()
     world.systems.hello_world.0 = "Goodbye, World!";
     world.update();
 }
+
+#[test]
+fn test_data_is_send_and_sync()
+{
+    fn is_send_sync<T: Send + Sync>() {}
+
+    // Hooks, indexes, resources and groups mustn't stop world data from being moved or shared
+    // between threads.
+    is_send_sync::<ecs::ComponentList<TestComponents, Position>>();
+    is_send_sync::<ecs::Aspect<TestComponents>>();
+    is_send_sync::<DataHelper>();
+
+    // Nor should observers stop a world from being sent to another thread.
+    fn send<T: Send>(value: T) -> T { value }
+    let mut world = World::<TestSystems>::new();
+    world.add_observer(ecs::Trigger::Create, aspect!(<TestComponents> all: [position]), |_, _, _| {});
+    let world = send(world);
+    assert_eq!(world.observers.len(), 1);
+}
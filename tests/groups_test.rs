@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate ecs;
+
+use std::sync::Arc;
+
+use ecs::{BuildData, DataHelper, ModifyData, Process, System, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Burning;
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] burning: Burning,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+            heat: Heat = Heat,
+        },
+        passive: {
+        }
+    }
+}
+
+/// Warms up everything in the "burning" group, without an aspect of its own.
+pub struct Heat;
+impl System for Heat { type Components = TestComponents; type Services = (); }
+impl Process for Heat
+{
+    fn process(&mut self, data: &mut DataHelper<TestComponents, ()>)
+    {
+        if let Some(burning) = data.entity_group("burning") {
+            for e in burning.iter() {
+                data.position[e].0 += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_entity_groups()
+{
+    let mut world = World::<TestSystems>::new();
+    let old = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.burning.add(&e, Burning);
+    });
+    let cold = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+
+    // Entities that already match are in the group straight away.
+    assert!(world.add_entity_group("burning", aspect!(<TestComponents> all: [burning])).is_ok());
+    assert!(world.add_entity_group("burning", aspect!(<TestComponents> all: [position])).is_err());
+    assert_eq!(world.data.groups.names(), vec!["burning"]);
+    let before = world.data.entity_group("burning").unwrap();
+    assert_eq!(before.len(), 1);
+    assert!(before.contains(&old));
+    assert!(!before.contains(&cold));
+
+    // Systems can read the group from the data helper.
+    world.update();
+    assert_eq!(world.data.with_entity_data(&old, |e, c| c.position[e]), Some(Position(1)));
+    assert_eq!(world.data.with_entity_data(&cold, |e, c| c.position[e]), Some(Position(0)));
+
+    // Membership follows modifications and removals, and held groups keep their old members.
+    world.modify_entity(cold, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.burning.insert(&e, Burning);
+    });
+    world.remove_entity(old);
+    world.flush_queue();
+    let after = world.data.entity_group("burning").unwrap();
+    assert_eq!(after.iter().map(|e| **e).collect::<Vec<_>>(), vec![cold]);
+    assert_eq!(before.iter().map(|e| **e).collect::<Vec<_>>(), vec![old]);
+
+    // Groups are shared until their members change.
+    drop(before);
+    assert!(Arc::ptr_eq(&after, &world.data.entity_group("burning").unwrap()));
+
+    assert!(world.remove_entity_group("burning"));
+    assert!(!world.remove_entity_group("burning"));
+    assert!(world.data.entity_group("burning").is_none());
+}
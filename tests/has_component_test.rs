@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate ecs;
+
+use std::marker::PhantomData;
+
+use ecs::{Aspect, BuildData, DataHelper, EntityIter, HasComponent, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Velocity(pub i32);
+
+components! {
+    #[has_component]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] velocity: Velocity,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+            motion: EntitySystem<Motion<TestComponents>> = EntitySystem::new(
+                Motion(PhantomData),
+                Aspect::all().with::<Position>().with::<Velocity>()
+            ),
+        },
+        passive: {
+        }
+    }
+}
+
+/// Moves entities along, for any manager with positions and velocities.
+pub struct Motion<C>(PhantomData<C>);
+impl<C> System for Motion<C> where C: HasComponent<Position> + HasComponent<Velocity>
+{
+    type Components = C;
+    type Services = ();
+}
+impl<C> EntityProcess for Motion<C> where C: HasComponent<Position> + HasComponent<Velocity>
+{
+    fn process(&mut self, entities: EntityIter<C>, data: &mut DataHelper<C, ()>)
+    {
+        for e in entities {
+            let velocity = data.list::<Velocity>()[e];
+            data.list_mut::<Position>()[e].0 += velocity.0;
+        }
+    }
+}
+
+#[test]
+fn test_components_has_component()
+{
+    let mut world = World::<TestSystems>::new();
+    let moving = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.velocity.add(&e, Velocity(3));
+    });
+    let still = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+    world.update();
+    world.update();
+
+    assert_eq!(world.data.with_entity_data(&moving, |e, c| c.position[e]), Some(Position(6)));
+    assert_eq!(world.data.with_entity_data(&still, |e, c| c.position[e]), Some(Position(0)));
+}
@@ -0,0 +1,108 @@
+#[macro_use]
+extern crate ecs;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use ecs::{BuildData, Entity, ModifyData, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+components! {
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()>;
+}
+
+#[test]
+fn test_component_hooks()
+{
+    let mut world = World::<TestSystems>::new();
+
+    // A side index from position to entity, kept up to date by the hooks.
+    let index = Arc::new(Mutex::new(HashMap::<i32, Entity>::new()));
+    let replaced = Arc::new(Mutex::new(Vec::new()));
+    {
+        let index = index.clone();
+        world.position.on_add(move |e, p| { index.lock().unwrap().insert(p.0, e); });
+    }
+    {
+        let (index, replaced) = (index.clone(), replaced.clone());
+        world.position.on_replace(move |e, old, new| {
+            index.lock().unwrap().remove(&old.0);
+            index.lock().unwrap().insert(new.0, e);
+            replaced.lock().unwrap().push((old.0, new.0));
+        });
+    }
+    {
+        let index = index.clone();
+        world.position.on_remove(move |_, p| { index.lock().unwrap().remove(&p.0); });
+    }
+
+    let a = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(1));
+    });
+    let b = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(2));
+    });
+    world.flush_queue();
+    assert_eq!(*index.lock().unwrap(), vec![(1, a), (2, b)].into_iter().collect());
+
+    world.with_entity_data(&a, |e, c| { c.position.set(&e, Position(3)); });
+    assert_eq!(*replaced.lock().unwrap(), vec![(1, 3)]);
+    assert_eq!(index.lock().unwrap().get(&3), Some(&a));
+    assert_eq!(index.lock().unwrap().get(&1), None);
+
+    world.modify_entity(b, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position.remove(&e);
+    });
+    assert_eq!(index.lock().unwrap().get(&2), None);
+
+    // Removing the entity clears its components through the hooks too.
+    world.remove_entity(a);
+    world.flush_queue();
+    assert!(index.lock().unwrap().is_empty());
+
+    world.position.clear_hooks();
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(4));
+    });
+    assert!(index.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_restore_keeps_hooks()
+{
+    let mut world = World::<TestSystems>::new();
+    let added = Arc::new(Mutex::new(Vec::new()));
+    {
+        let added = added.clone();
+        world.position.on_add(move |_, p| { added.lock().unwrap().push(("before", p.0)); });
+    }
+    let snapshot = world.snapshot();
+
+    // Hooks registered after the snapshot survive restoring it.
+    {
+        let added = added.clone();
+        world.position.on_add(move |_, p| { added.lock().unwrap().push(("after", p.0)); });
+    }
+    world.restore(&snapshot);
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(1));
+    });
+    assert_eq!(*added.lock().unwrap(), vec![("after", 1)]);
+
+    // Likewise, hooks cleared since the snapshot stay cleared.
+    world.position.clear_hooks();
+    world.restore(&snapshot);
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(2));
+    });
+    assert_eq!(*added.lock().unwrap(), vec![("after", 1)]);
+}
@@ -0,0 +1,73 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, ModifyData, World};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Team(pub u8);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NetworkId(pub u32);
+
+components! {
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot_indexed] team: Team,
+        #[cold_indexed] network_id: NetworkId,
+        #[hot] score: u32,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()>;
+}
+
+fn spawn(world: &mut World<TestSystems>, team: u8, id: u32) -> ecs::Entity
+{
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.team.add(&e, Team(team));
+        c.network_id.add(&e, NetworkId(id));
+    })
+}
+
+#[test]
+fn test_indexed_lists()
+{
+    let mut world = World::<TestSystems>::new();
+    let a = spawn(&mut world, 1, 10);
+    let b = spawn(&mut world, 2, 20);
+    let c = spawn(&mut world, 2, 30);
+    world.flush_queue();
+
+    assert!(world.team.is_indexed());
+    // Lists that aren't indexed can't be searched.
+    assert!(!world.score.is_indexed());
+    assert!(world.score.search().is_none());
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![b, c]);
+    assert_eq!(world.team.search().unwrap().find(&Team(3)), vec![]);
+    assert_eq!(world.network_id.search().unwrap().find_first(&NetworkId(20)), Some(b));
+    assert_eq!(world.network_id.search().unwrap().find_range(NetworkId(15)..), vec![b, c]);
+
+    // Changes through `set`, `borrow`, indexing and `remove` are all picked up.
+    world.with_entity_data(&a, |e, c| { c.team.set(&e, Team(2)); });
+    assert_eq!(world.team.search().unwrap().find(&Team(1)), vec![]);
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![a, b, c]);
+    world.with_entity_data(&b, |e, c| { c.team.borrow(&e).unwrap().0 = 3; });
+    world.with_entity_data(&c, |e, c| { c.network_id[e].0 = 5; });
+    assert_eq!(world.team.search().unwrap().find(&Team(3)), vec![b]);
+    assert_eq!(world.network_id.search().unwrap().find_range(..NetworkId(15)), vec![c, a]);
+    world.modify_entity(a, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.team.remove(&e);
+    });
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![c]);
+
+    // Copies of the list, such as in snapshots, are indexed separately.
+    let snapshot = world.snapshot();
+    world.remove_entity(c);
+    world.flush_queue();
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![]);
+    assert_eq!(world.network_id.search().unwrap().find_first(&NetworkId(5)), None);
+    world.restore(&snapshot);
+    assert_eq!(world.team.search().unwrap().find(&Team(2)), vec![c]);
+    assert_eq!(world.network_id.search().unwrap().find_first(&NetworkId(5)), Some(c));
+}
@@ -0,0 +1,201 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, DataHelper, Entity, EntityData, EntityIter, System, World};
+use ecs::system::{InteractProcess, InteractSystem, MultiInteractProcess, MultiInteractSystem};
+use ecs::system::{SpatialInteractSystem, SpatialProcess};
+use ecs::system::interact::Pairs;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position
+{
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bullet;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Target;
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] bullet: Bullet,
+        #[cold] target: Target,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+            hits: SpatialInteractSystem<Hits> = SpatialInteractSystem::new(
+                Hits(Vec::new()),
+                aspect!(<TestComponents> all: [position, bullet]),
+                aspect!(<TestComponents> all: [position, target]),
+                1.5,
+                |e, c: &TestComponents| (c.position[e].x, c.position[e].y)
+            ),
+            bumps: SpatialInteractSystem<Hits> = SpatialInteractSystem::same(
+                Hits(Vec::new()),
+                aspect!(<TestComponents> all: [position, target]),
+                1.5,
+                |e, c: &TestComponents| (c.position[e].x, c.position[e].y)
+            ),
+            targets: InteractSystem<Targets> = InteractSystem::same(
+                Targets { activated: 0, pairs: Vec::new() },
+                aspect!(<TestComponents> all: [target])
+            ),
+            groups: MultiInteractSystem<Groups, 3> = MultiInteractSystem::new(Groups(Vec::new()), [
+                aspect!(<TestComponents> all: [bullet]),
+                aspect!(<TestComponents> all: [target]),
+                aspect!(<TestComponents> all: [bullet, target]),
+            ]),
+        },
+        passive: {
+        }
+    }
+}
+
+/// Records every pair it's given.
+pub struct Hits(pub Vec<(Entity, Entity)>);
+impl System for Hits { type Components = TestComponents; type Services = (); }
+impl SpatialProcess for Hits
+{
+    fn process(&mut self, pairs: Pairs<TestComponents>, _: &mut DataHelper<TestComponents, ()>)
+    {
+        self.0.extend(pairs.map(|(a, b)| (**a, **b)));
+    }
+}
+
+/// Counts activations, and records every pair of targets.
+pub struct Targets
+{
+    pub activated: usize,
+    pub pairs: Vec<(Entity, Entity)>,
+}
+impl System for Targets
+{
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.activated += 1;
+    }
+}
+impl InteractProcess for Targets
+{
+    fn process(&mut self, a: EntityIter<TestComponents>, b: EntityIter<TestComponents>, _: &mut DataHelper<TestComponents, ()>)
+    {
+        assert_eq!(a.clone().count(), b.clone().count());
+        self.pairs.extend(Pairs::new(a, b).map(|(a, b)| (**a, **b)));
+    }
+}
+
+/// Counts the entities in each group.
+pub struct Groups(pub Vec<[usize; 3]>);
+impl System for Groups { type Components = TestComponents; type Services = (); }
+impl MultiInteractProcess<3> for Groups
+{
+    fn process(&mut self, groups: [EntityIter<TestComponents>; 3], _: &mut DataHelper<TestComponents, ()>)
+    {
+        let [bullets, targets, both] = groups;
+        self.0.push([bullets.count(), targets.count(), both.count()]);
+    }
+}
+
+fn spawn(world: &mut World<TestSystems>, x: f32, y: f32, bullet: bool, target: bool) -> Entity
+{
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x, y });
+        if bullet { c.bullet.add(&e, Bullet); }
+        if target { c.target.add(&e, Target); }
+    })
+}
+
+#[test]
+fn test_interactions()
+{
+    let mut world = World::<TestSystems>::new();
+    let bullet = spawn(&mut world, 0.0, 0.0, true, false);
+    let near = spawn(&mut world, 1.0, 1.0, false, true);
+    let edge = spawn(&mut world, -1.5, 0.0, false, true);
+    let far = spawn(&mut world, 1.5, 1.5, false, true);
+    spawn(&mut world, 10.0, 0.0, false, true);
+    // Matching both aspects doesn't make an entity hit itself.
+    let both = spawn(&mut world, 20.0, 0.0, true, true);
+    let other = spawn(&mut world, 20.5, 0.0, false, true);
+
+    world.update();
+    let mut hits = world.systems.hits.0.clone();
+    hits.sort();
+    assert_eq!(hits, vec![(bullet, near), (bullet, edge), (both, other)]);
+
+    // With a single aspect, each pair is seen once.
+    let mut bumps = world.systems.bumps.0.clone();
+    bumps.sort();
+    assert_eq!(bumps, vec![(near, far), (both, other)]);
+    assert_eq!(world.systems.targets.activated, 6);
+    assert_eq!(world.systems.targets.pairs.len(), 15);
+    assert!(world.systems.targets.pairs.iter().all(|&(a, b)| a < b));
+    assert_eq!(world.systems.groups.0, vec![[2, 6, 1]]);
+
+    world.systems.hits.0.clear();
+    world.systems.hits.set_radius(0.5);
+    world.update();
+    assert_eq!(world.systems.hits.0, vec![(both, other)]);
+
+    world.systems.hits.0.clear();
+    world.remove_entity(both);
+    world.update();
+    assert_eq!(world.systems.hits.0, vec![]);
+    assert_eq!(world.systems.groups.0[2], [1, 5, 0]);
+}
+
+#[test]
+fn test_spatial_far_from_origin()
+{
+    // Positions too far out for their cell to fit in an i32 share the outermost cells.
+    let mut world = World::<TestSystems>::new();
+    let a = spawn(&mut world, 4.0e9, 0.0, false, true);
+    let b = spawn(&mut world, 4.0e9, 1.0, false, true);
+    let c = spawn(&mut world, -4.0e9, -4.0e9, false, true);
+    let d = spawn(&mut world, -4.0e9, -4.0e9, false, true);
+    world.update();
+    let mut bumps = world.systems.bumps.0.clone();
+    bumps.sort();
+    assert_eq!(bumps, vec![(a, b), (c, d)]);
+}
+
+#[test]
+fn test_spatial_moving_entities()
+{
+    // Cells are reused as entities move between them, without mixing up who is where.
+    let mut world = World::<TestSystems>::new();
+    let a = spawn(&mut world, 0.0, 0.0, false, true);
+    let b = spawn(&mut world, 0.5, 0.0, false, true);
+    let moves = [(100.0, 0.0), (100.0, 50.0), (-0.5, 0.5), (-30.0, 0.0), (1.0, 1.0)];
+    for &(x, y) in &moves {
+        world.systems.bumps.0.clear();
+        world.with_entity_data(&b, |e, c| { c.position[e] = Position { x, y }; });
+        world.update();
+        let near = x * x + y * y <= 1.5 * 1.5;
+        assert_eq!(world.systems.bumps.0, if near { vec![(a, b)] } else { vec![] });
+    }
+}
+
+#[test]
+fn test_spatial_system_is_send_and_sync()
+{
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<SpatialInteractSystem<Hits>>();
+}
+
+#[test]
+#[should_panic]
+fn test_spatial_zero_radius()
+{
+    let mut world = World::<TestSystems>::new();
+    world.systems.hits.set_radius(0.0);
+}
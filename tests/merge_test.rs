@@ -0,0 +1,112 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, ComponentList, Entity, EntityData, MapEntities, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Follow(pub Entity);
+
+impl MapEntities for Follow
+{
+    fn map_entities(&mut self, map: &ecs::EntityMap)
+    {
+        self.0.map_entities(map);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Loot(pub u32);
+
+components! {
+    #[dynamic(extra)]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] follow: Follow,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            tracker: EntitySystem<Tracker> = EntitySystem::new(Tracker(0), aspect!(<TestComponents> all: [position])),
+        }
+    }
+}
+
+pub struct Tracker(pub usize);
+impl System for Tracker
+{
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ())
+    {
+        self.0 += 1;
+    }
+}
+impl EntityProcess for Tracker
+{
+    fn process(&mut self, _: ecs::EntityIter<TestComponents>, _: &mut ecs::DataHelper<TestComponents, ()>) {}
+}
+
+fn at(x: i32) -> impl FnOnce(BuildData<TestComponents>, &mut TestComponents)
+{
+    move |e, c| { c.position.add(&e, Position(x)); }
+}
+
+#[test]
+fn test_merge()
+{
+    let mut world = World::<TestSystems>::new();
+    let existing = world.create_entity(at(0));
+    world.flush_queue();
+
+    let mut chunk = World::<TestSystems>::new();
+    chunk.extra.register("loot", ComponentList::<TestComponents, Loot>::cold());
+    let leader = chunk.create_entity(at(10));
+    let follower = chunk.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(11));
+        c.follow.add(&e, Follow(leader));
+        c.extra.list_mut::<Loot>().unwrap().add(&e, Loot(5));
+    });
+    let doomed = chunk.create_entity(at(12));
+    chunk.set_name(&leader, "leader");
+    chunk.add_tag(&follower, "minion");
+    chunk.add_tag(&doomed, "minion");
+    chunk.remove_entity(doomed);
+    world.set_name(&existing, "leader");
+    world.add_tag(&existing, "boss");
+
+    let map = world.merge_with(chunk, |map, e, c| c.follow.map_entities(&e, map));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&doomed), None);
+    let (leader, follower) = (map.map(leader), map.map(follower));
+    assert!(leader != existing && follower != existing && leader != follower);
+
+    // The moved entities went through this world's activation.
+    assert_eq!(world.systems.tracker.0, 3);
+    assert_eq!(world.entities().count(), 3);
+    assert_eq!(world.with_entity_data(&leader, |e, c| c.position[e]), Some(Position(10)));
+    assert_eq!(world.with_entity_data(&follower, |e, c| (c.position[e], c.follow[e])),
+        Some((Position(11), Follow(leader))));
+    assert_eq!(world.with_entity_data(&existing, |e, c| (c.position[e], c.follow.has(&e))),
+        Some((Position(0), false)));
+
+    // Names and tags are moved with their entities, under the new identifiers.
+    assert_eq!(world.names().entity("leader"), Some(leader));
+    assert_eq!(world.names().name(&existing), None);
+    let minion = world.names().tag("minion").unwrap();
+    assert_eq!(world.names().tagged(minion).collect::<Vec<_>>(), vec![follower]);
+    let boss = world.names().tag("boss").unwrap();
+    assert_eq!(world.names().tagged(boss).collect::<Vec<_>>(), vec![existing]);
+
+    // Dynamic lists come across too, registered if the target didn't have them.
+    assert_eq!(world.extra.names(), vec!["loot"]);
+    assert_eq!(world.with_entity_data(&follower, |e, c| c.extra.list::<Loot>().unwrap().get(&e)), Some(Some(Loot(5))));
+}
@@ -0,0 +1,66 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()>;
+}
+
+fn at(x: i32) -> impl FnOnce(BuildData<TestComponents>, &mut TestComponents)
+{
+    move |e, c| { c.position.add(&e, Position(x)); }
+}
+
+#[test]
+fn test_names_and_tags()
+{
+    let mut world = World::<TestSystems>::new();
+    let player = world.create_entity(at(0));
+    let camera = world.create_entity(at(1));
+    let goblin = world.create_entity(at(2));
+    let orc = world.create_entity(at(3));
+
+    assert_eq!(world.set_name(&player, "player"), None);
+    assert_eq!(world.set_name(&camera, "camera"), None);
+    assert_eq!(world.names().entity("player"), Some(player));
+    assert_eq!(world.names().name(&camera), Some("camera"));
+
+    // Names are unique, so giving one to another entity takes it away from the first.
+    assert_eq!(world.set_name(&goblin, "player"), Some(player));
+    assert_eq!(world.names().name(&player), None);
+    assert_eq!(world.set_name(&player, "player"), Some(goblin));
+    assert_eq!(world.remove_name(&goblin), None);
+
+    let enemy = world.add_tag(&goblin, "enemy").unwrap();
+    assert_eq!(world.add_tag(&orc, "enemy"), Some(enemy));
+    world.add_tag(&orc, "large");
+    assert_eq!(world.names().tag("enemy"), Some(enemy));
+    assert_eq!(world.names().tag_name(enemy), "enemy");
+    assert_eq!(world.names().tagged(enemy).collect::<Vec<_>>(), vec![goblin, orc]);
+    assert!(world.names().has_tag(&orc, enemy));
+    assert_eq!(world.names().tags_of(&orc).count(), 2);
+    assert!(world.remove_tag(&goblin, "enemy"));
+    assert!(!world.remove_tag(&goblin, "enemy"));
+    assert!(!world.remove_tag(&goblin, "unknown"));
+
+    // Removed entities leave the index once the queue is flushed.
+    world.remove_entity(orc);
+    world.remove_entity(player);
+    world.flush_queue();
+    assert_eq!(world.names().entity("player"), None);
+    assert_eq!(world.names().tagged(enemy).count(), 0);
+    assert_eq!(world.names().tags_of(&orc).count(), 0);
+    assert_eq!(world.set_name(&orc, "ghost"), None);
+    assert_eq!(world.names().entity("ghost"), None);
+    assert_eq!(world.add_tag(&orc, "enemy"), None);
+}
@@ -0,0 +1,90 @@
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, ModifyData, Trigger, World};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Burning;
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] burning: Burning,
+    }
+}
+
+#[derive(Default)]
+pub struct TestServices
+{
+    pub log: Vec<String>,
+}
+
+impl ecs::ServiceManager for TestServices {}
+
+systems! {
+    struct TestSystems<TestComponents, TestServices>;
+}
+
+fn log(world: &mut World<TestSystems>, trigger: Trigger, name: &'static str)
+{
+    world.add_observer(trigger, aspect!(<TestComponents> all: [burning]), move |e, c, s: &mut TestServices| {
+        s.log.push(format!("{} {}", name, c.position[e].0));
+    });
+}
+
+#[test]
+fn test_observers()
+{
+    let mut world = World::<TestSystems>::new();
+    let old = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.burning.add(&e, Burning);
+    });
+
+    for &(trigger, name) in &[(Trigger::Create, "create"), (Trigger::Modify, "modify"), (Trigger::Remove, "remove"),
+        (Trigger::Enter, "enter"), (Trigger::Exit, "exit")] {
+        log(&mut world, trigger, name);
+    }
+
+    // Entities that matched before the observers were added aren't reported as new.
+    assert!(world.services.log.is_empty());
+    world.modify_entity(old, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position.insert(&e, Position(1));
+    });
+    assert_eq!(world.services.log, vec!["modify 1"]);
+
+    // Adding the component enters the aspect, and removing it exits.
+    world.services.log.clear();
+    let new = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(2));
+    });
+    world.flush_queue();
+    assert!(world.services.log.is_empty());
+    world.modify_entity(new, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.burning.insert(&e, Burning);
+    });
+    world.modify_entity(new, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.burning.remove(&e);
+    });
+    assert_eq!(world.services.log, vec!["enter 2", "exit 2"]);
+
+    // Creation and removal fire during the flush.
+    world.services.log.clear();
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(3));
+        c.burning.add(&e, Burning);
+    });
+    world.remove_entity(old);
+    assert!(world.services.log.is_empty());
+    world.flush_queue();
+    assert_eq!(world.services.log, vec!["create 3", "enter 3", "remove 1", "exit 1"]);
+
+    assert_eq!(world.observers.len(), 5);
+    let id = world.add_observer(Trigger::Create, aspect!(<TestComponents> all: [position]), |_, _, _| {});
+    assert!(world.remove_observer(id));
+    assert!(!world.remove_observer(id));
+    assert_eq!(world.observers.len(), 5);
+}
@@ -0,0 +1,129 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{Entity, EntityData, World};
+use ecs::prefab::{Prefab, PrefabError, PrefabRegistry};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub u32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mount(pub Entity);
+
+components! {
+    #[builder(EntityInit)]
+    struct TestComponents {
+        #[hot] health: Health,
+        #[hot] position: Position,
+        #[cold] name: &'static str,
+        #[cold] mount: Mount,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+        }
+    }
+}
+
+fn prefabs() -> PrefabRegistry<TestComponents, EntityInit>
+{
+    let mut prefabs = PrefabRegistry::new();
+    // Registered before its parent, which is only looked up when used.
+    prefabs.insert("goblin_chief", Prefab::inherit("goblin", EntityInit {
+        health: Some(Health(30)),
+        name: Some("chief"),
+        ..Default::default()
+    }));
+    prefabs.insert("goblin", Prefab::new(EntityInit {
+        health: Some(Health(10)),
+        position: Some(Position(0)),
+        name: Some("goblin"),
+        ..Default::default()
+    }));
+
+    let mut rider = Prefab::inherit("goblin", Default::default());
+    let wolf = rider.add_part(EntityInit {
+        health: Some(Health(15)),
+        name: Some("wolf"),
+        ..Default::default()
+    });
+    rider.link(0, move |e: EntityData<TestComponents>, c: &mut TestComponents, parts: &[Entity]| {
+        c.mount.set(&e, Mount(parts[wolf]));
+    });
+    prefabs.insert("wolf_rider", rider);
+
+    prefabs.insert("loop_a", Prefab::inherit("loop_b", Default::default()));
+    prefabs.insert("loop_b", Prefab::inherit("loop_a", Default::default()));
+    prefabs
+}
+
+#[test]
+fn test_prefab_instances()
+{
+    let prefabs = prefabs();
+    let mut world = World::<TestSystems>::new();
+
+    let goblin = world.create_entity(prefabs.instance("goblin", Default::default()).unwrap());
+    let chief = world.create_entity(prefabs.instance("goblin_chief", EntityInit {
+        position: Some(Position(4)),
+        ..Default::default()
+    }).unwrap());
+    world.flush_queue();
+
+    assert_eq!(world.with_entity_data(&goblin, |e, c| (c.health[e], c.position[e], c.name[e])),
+        Some((Health(10), Position(0), "goblin")));
+    assert_eq!(world.with_entity_data(&chief, |e, c| (c.health[e], c.position[e], c.name[e])),
+        Some((Health(30), Position(4), "chief")));
+
+    assert_eq!(prefabs.instance("orc", Default::default()).err(), Some(PrefabError::Unknown("orc".to_string())));
+    assert_eq!(prefabs.instance("loop_a", Default::default()).err(), Some(PrefabError::Cycle("loop_a".to_string())));
+}
+
+#[test]
+fn test_multi_entity_prefabs()
+{
+    let prefabs = prefabs();
+    let mut world = World::<TestSystems>::new();
+
+    let spawned = prefabs.spawn("wolf_rider", EntityInit { name: Some("scout"), ..Default::default() }, &mut world.data).unwrap();
+    assert_eq!(spawned.len(), 2);
+    let (rider, wolf) = (spawned[0], spawned[1]);
+    world.flush_queue();
+
+    assert_eq!(world.with_entity_data(&rider, |e, c| (c.name[e], c.health[e], c.mount.get(&e))),
+        Some(("scout", Health(10), Some(Mount(wolf)))));
+    assert_eq!(world.with_entity_data(&wolf, |e, c| (c.name[e], c.position.has(&e), c.mount.has(&e))),
+        Some(("wolf", false, false)));
+
+    // Each spawn links its own parts together.
+    let again = prefabs.spawn("wolf_rider", Default::default(), &mut world.data).unwrap();
+    assert_eq!(world.with_entity_data(&again[0], |e, c| c.mount.get(&e)), Some(Some(Mount(again[1]))));
+}
+
+#[test]
+fn test_prefabs_as_resource()
+{
+    type Prefabs = PrefabRegistry<TestComponents, EntityInit>;
+    let mut world = World::<TestSystems>::new();
+    assert!(world.resources.insert(prefabs()).is_none());
+
+    let init = world.resources.get::<Prefabs>().unwrap().instance("goblin", Default::default()).unwrap();
+    let goblin = world.create_entity(init);
+
+    // Spawning needs the data as well, so the registry is taken out while it's used.
+    let prefabs = world.resources.remove::<Prefabs>().unwrap();
+    let spawned = prefabs.spawn("wolf_rider", Default::default(), &mut world.data).unwrap();
+    world.resources.insert(prefabs);
+    world.flush_queue();
+
+    assert_eq!(world.with_entity_data(&goblin, |e, c| c.health[e]), Some(Health(10)));
+    assert_eq!(world.with_entity_data(&spawned[0], |e, c| c.mount.get(&e)), Some(Some(Mount(spawned[1]))));
+}
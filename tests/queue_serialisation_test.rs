@@ -0,0 +1,103 @@
+#![cfg(feature="serialisation")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate cereal;
+
+use cereal::{CerealData, CerealResult};
+use ecs::{BuildData, EntityData, System, World};
+use std::io::{Cursor, Read, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl_cereal_data!(Position, x, y);
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+unsafe impl CerealData for TestComponents {
+    fn write(&self, w: &mut dyn Write) -> CerealResult<()> {
+        self.position.write(w)
+    }
+
+    fn read(r: &mut dyn Read) -> CerealResult<TestComponents> {
+        Ok(TestComponents {
+            position: try!(CerealData::read(r)),
+        })
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            counter: ActiveCount = ActiveCount(0),
+        }
+    }
+}
+
+pub struct ActiveCount(pub usize);
+impl System for ActiveCount {
+    type Components = TestComponents;
+    type Services = ();
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.0 += 1;
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.0 -= 1;
+    }
+}
+
+fn build(x: f32) -> impl FnOnce(BuildData<TestComponents>, &mut TestComponents) {
+    move |e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x, y: 0.0 });
+    }
+}
+
+#[test]
+fn test_save_with_pending_events() {
+    let mut world = World::<TestSystems>::new();
+
+    let kept = world.create_entity(build(1.0));
+    let removed = world.create_entity(build(2.0));
+    world.flush_queue();
+
+    world.set_name(&kept, "kept");
+    world.add_tag(&kept, "saved");
+    world.add_tag(&removed, "saved");
+
+    // Leave a build and a removal waiting in the queue.
+    world.create_entity(build(3.0));
+    world.remove_entity(removed);
+    assert_eq!(world.systems.counter.0, 2);
+
+    let mut store = Cursor::new(Vec::new());
+    world.save(&mut store).unwrap();
+    store.set_position(0);
+
+    let mut loaded: World<TestSystems> = World::load(&mut store).unwrap();
+
+    // Only the entities which were already active get activated by loading.
+    assert_eq!(loaded.systems.counter.0, 2);
+
+    loaded.flush_queue();
+    world.flush_queue();
+    assert_eq!(loaded.systems.counter.0, world.systems.counter.0);
+    assert_eq!(loaded.entities().count(), 2);
+    assert!(loaded.with_entity_data(&removed, |_, _| ()).is_none());
+    assert_eq!(loaded.with_entity_data(&kept, |e, c| c.position[e]), Some(Position { x: 1.0, y: 0.0 }));
+
+    // Names and tags are saved too, and the removed entity's are cleared by the flush.
+    assert_eq!(loaded.names().entity("kept"), Some(kept));
+    let saved = loaded.names().tag("saved").unwrap();
+    assert_eq!(loaded.names().tagged(saved).collect::<Vec<_>>(), vec![kept]);
+}
@@ -0,0 +1,193 @@
+#![cfg(feature="serialisation")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate cereal;
+
+use cereal::{CerealData, CerealError, CerealResult};
+use ecs::{BuildData, DataHelper, Entity, EntityIter, ModifyData, ServiceManager, System, World};
+use ecs::replay::{Command, Recorder, ReplayError, Replayer};
+use ecs::system::{EntityProcess, EntitySystem};
+use std::io::{Read, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub f32);
+
+impl_cereal_data!(Position(), a);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Velocity(pub f32);
+
+impl_cereal_data!(Velocity(), a);
+
+components! {
+    struct TestComponents {
+        #[hot] position: Position,
+        #[hot] velocity: Velocity,
+    }
+}
+
+unsafe impl CerealData for TestComponents {
+    fn write(&self, w: &mut dyn Write) -> CerealResult<()> {
+        self.position.write(w)?;
+        self.velocity.write(w)
+    }
+
+    fn read(r: &mut dyn Read) -> CerealResult<TestComponents> {
+        Ok(TestComponents {
+            position: CerealData::read(r)?,
+            velocity: CerealData::read(r)?,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct TestServices {
+    pub drag: f32,
+}
+
+impl_cereal_data!(TestServices, drag);
+
+impl ServiceManager for TestServices {}
+
+systems! {
+    struct TestSystems<TestComponents, TestServices> {
+        active: {
+            motion: EntitySystem<Motion> = EntitySystem::new(Motion, aspect!(<TestComponents> all: [position, velocity])),
+        },
+        passive: {
+        }
+    }
+}
+
+pub struct Motion;
+impl System for Motion {
+    type Components = TestComponents;
+    type Services = TestServices;
+}
+impl EntityProcess for Motion {
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, TestServices>) {
+        let drag = data.services.drag;
+        for e in entities {
+            data.velocity[e].0 *= 1.0 - drag;
+            let velocity = data.velocity[e].0;
+            data.position[e].0 += velocity;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Spawn(f32),
+    Push(Entity, f32),
+    Despawn(Entity),
+    SetDrag(f32),
+}
+
+unsafe impl CerealData for Input {
+    fn write(&self, w: &mut dyn Write) -> CerealResult<()> {
+        match *self {
+            Input::Spawn(v) => { 0u8.write(w)?; v.write(w) },
+            Input::Push(e, v) => { 1u8.write(w)?; e.write(w)?; v.write(w) },
+            Input::Despawn(e) => { 2u8.write(w)?; e.write(w) },
+            Input::SetDrag(v) => { 3u8.write(w)?; v.write(w) },
+        }
+    }
+
+    fn read(r: &mut dyn Read) -> CerealResult<Input> {
+        match u8::read(r)? {
+            0 => Ok(Input::Spawn(CerealData::read(r)?)),
+            1 => Ok(Input::Push(CerealData::read(r)?, CerealData::read(r)?)),
+            2 => Ok(Input::Despawn(CerealData::read(r)?)),
+            3 => Ok(Input::SetDrag(CerealData::read(r)?)),
+            _ => Err(CerealError::Msg("Unknown input".to_string())),
+        }
+    }
+}
+
+impl Command<TestSystems> for Input {
+    fn apply(&self, world: &mut World<TestSystems>) {
+        match *self {
+            Input::Spawn(velocity) => {
+                world.create_entity(move |e: BuildData<TestComponents>, c: &mut TestComponents| {
+                    c.position.add(&e, Position(0.0));
+                    c.velocity.add(&e, Velocity(velocity));
+                });
+            },
+            Input::Push(entity, amount) => world.modify_entity(entity, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+                c.velocity[e].0 += amount;
+            }),
+            Input::Despawn(entity) => world.remove_entity(entity),
+            Input::SetDrag(drag) => world.services.drag = drag,
+        }
+    }
+}
+
+#[test]
+fn test_record_and_replay() {
+    let mut world = World::<TestSystems>::new();
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(5.0));
+        c.velocity.add(&e, Velocity(1.0));
+    });
+    world.update();
+
+    let mut recorder = Recorder::start(&mut world).unwrap();
+    recorder.apply(&mut world, Input::SetDrag(0.1));
+    recorder.apply(&mut world, Input::Spawn(2.0));
+    recorder.update(&mut world).unwrap();
+    let spawned = world.entities().last().map(|e| **e).unwrap();
+    recorder.apply(&mut world, Input::Push(spawned, 0.5));
+    recorder.update(&mut world).unwrap();
+    recorder.update(&mut world).unwrap();
+    recorder.apply(&mut world, Input::Despawn(spawned));
+    recorder.update(&mut world).unwrap();
+    let recording = recorder.finish();
+    assert_eq!(recording.frames.len(), 4);
+
+    // Round-trip the recording through bytes, as if it were attached to a bug report.
+    let mut bytes = Vec::new();
+    recording.write(&mut bytes).unwrap();
+    let recording = CerealData::read(&mut &bytes[..]).unwrap();
+
+    let replayed: World<TestSystems> = Replayer::new(&recording).unwrap().run().unwrap();
+    assert_eq!(ecs::replay::state_hash(&replayed.data).unwrap(), ecs::replay::state_hash(&world.data).unwrap());
+
+    // Changing an input makes the replay diverge from the recorded hashes.
+    let mut tampered = recording.clone();
+    tampered.frames[1].commands[0] = Input::Push(spawned, 0.6);
+    let mut replayer: Replayer<TestSystems, Input> = Replayer::new(&tampered).unwrap();
+    assert!(replayer.step().unwrap());
+    match replayer.step() {
+        Err(ReplayError::Desync { frame: 1, .. }) => (),
+        other => panic!("Expected a desync at frame 1, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_state_hash_is_fixed() {
+    // The hash mustn't depend on the toolchain, or old recordings would report false desyncs.
+    let world = World::<TestSystems>::new();
+    assert_eq!(ecs::replay::state_hash(&world.data).unwrap(), 0xaaf1_d96c_3297_917d);
+}
+
+#[test]
+fn test_state_hash_survives_save_and_load() {
+    let mut world = World::<TestSystems>::new();
+    let entities: Vec<Entity> = (0..20).map(|i| world.create_entity(move |e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(i as f32));
+    })).collect();
+    world.flush_queue();
+    for (i, entity) in entities.iter().enumerate() {
+        world.set_name(entity, &format!("entity {}", i));
+        world.add_tag(entity, if i % 2 == 0 { "even" } else { "odd" });
+        world.add_tag(entity, "numbered");
+    }
+
+    // Names and tags are kept in hash maps, which mustn't decide the order they're written in.
+    let mut bytes = Vec::new();
+    world.save(&mut bytes).unwrap();
+    let loaded: World<TestSystems> = World::load(&mut &bytes[..]).unwrap();
+    assert_eq!(ecs::replay::state_hash(&loaded.data).unwrap(), ecs::replay::state_hash(&world.data).unwrap());
+}
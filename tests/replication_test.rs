@@ -0,0 +1,213 @@
+#![cfg(feature="serialisation")]
+
+#[macro_use]
+extern crate ecs;
+#[macro_use]
+extern crate cereal;
+
+use ecs::{BuildData, DataHelper, EntityData, EntityIter, ModifyData, System, World};
+use ecs::replication::{Loopback, ReplicationClient, ReplicationServer};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl_cereal_data!(Position, x, y);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub u32);
+
+impl_cereal_data!(Health(), a);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ServerOnly;
+
+components! {
+    #[derive(Clone)]
+    #[replicated(position, health)]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] health: Health,
+        #[cold] server_only: ServerOnly,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()>;
+}
+
+systems! {
+    struct TrackedSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+            tracker: EntitySystem<Tracker> = EntitySystem::new(Tracker::default(), aspect!(<TestComponents> all: [position])),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Tracker {
+    pub activated: usize,
+    pub reactivated: usize,
+}
+
+impl System for Tracker {
+    type Components = TestComponents;
+    type Services = ();
+
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.activated += 1;
+    }
+
+    fn reactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut ()) {
+        self.reactivated += 1;
+    }
+}
+
+impl EntityProcess for Tracker {
+    fn process(&mut self, _: EntityIter<TestComponents>, _: &mut DataHelper<TestComponents, ()>) {}
+}
+
+#[test]
+fn test_loopback_replication() {
+    let mut server = World::<TestSystems>::new();
+    let mut client = World::<TestSystems>::new();
+    let mut replication = ReplicationServer::new();
+    let (server_end, client_end) = Loopback::pair();
+    replication.connect(server_end);
+    let mut receiver = ReplicationClient::new(client_end);
+
+    // Make sure ids don't line up between the two worlds.
+    client.create_entity(());
+
+    let player = server.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x: 1.0, y: 2.0 });
+        c.health.add(&e, Health(10));
+        c.server_only.add(&e, ServerOnly);
+    });
+    let rock = server.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x: 5.0, y: 5.0 });
+    });
+    server.flush_queue();
+
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    client.flush_queue();
+
+    let local_player = receiver.local_entity(&player).unwrap();
+    assert!(local_player != player);
+    assert_eq!(client.with_entity_data(&local_player, |e, c| {
+        (c.position.get(&e), c.health.get(&e), c.server_only.has(&e))
+    }), Some((Some(Position { x: 1.0, y: 2.0 }), Some(Health(10)), false)));
+
+    // Nothing changed, so nothing is sent.
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 0);
+
+    server.modify_entity(player, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position[e].x = 3.0;
+        c.health.remove(&e);
+    });
+    server.remove_entity(rock);
+    server.flush_queue();
+
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    client.flush_queue();
+
+    assert!(receiver.local_entity(&rock).is_none());
+    assert_eq!(client.entities().count(), 2);
+    assert_eq!(client.with_entity_data(&local_player, |e, c| {
+        (c.position.get(&e), c.health.get(&e))
+    }), Some((Some(Position { x: 3.0, y: 2.0 }), None)));
+}
+
+#[test]
+fn test_replicate_tracked_changes() {
+    let mut server = World::<TestSystems>::new();
+    let mut client = World::<TestSystems>::new();
+    let mut replication = ReplicationServer::new();
+    let (server_end, client_end) = Loopback::pair();
+    replication.connect(server_end);
+    let mut receiver = ReplicationClient::new(client_end);
+
+    let player = server.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x: 1.0, y: 2.0 });
+    });
+    server.flush_queue();
+    replication.replicate(&server.data).unwrap();
+    receiver.receive(&mut client).unwrap();
+    client.flush_queue();
+    let local_player = receiver.local_entity(&player).unwrap();
+    let before = server.snapshot();
+
+    // Changes made by indexing, outside of `modify_entity`, are still picked up.
+    server.data.with_entity_data(&player, |e, c| c.position[e].x = 7.0);
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    assert_eq!(client.with_entity_data(&local_player, |e, c| c.position[e].x), Some(7.0));
+
+    // Restoring a snapshot replaces the lists, so everything is compared again.
+    server.restore(&before);
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    assert_eq!(client.with_entity_data(&local_player, |e, c| c.position[e].x), Some(1.0));
+
+    // A client connecting later is sent the whole world.
+    let mut late = World::<TestSystems>::new();
+    let (server_end, client_end) = Loopback::pair();
+    replication.connect(server_end);
+    let mut late_receiver = ReplicationClient::new(client_end);
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 0);
+    assert_eq!(late_receiver.receive(&mut late).unwrap(), 1);
+    late.flush_queue();
+    assert_eq!(late.entities().count(), 1);
+}
+
+#[test]
+fn test_receive_creation_and_update_together() {
+    let mut server = World::<TestSystems>::new();
+    let mut client = World::<TrackedSystems>::new();
+    let mut replication = ReplicationServer::new();
+    let (server_end, client_end) = Loopback::pair();
+    replication.connect(server_end);
+    let mut receiver = ReplicationClient::new(client_end);
+
+    let player = server.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position { x: 1.0, y: 2.0 });
+    });
+    server.flush_queue();
+    replication.replicate(&server.data).unwrap();
+    server.modify_entity(player, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position[e].x = 3.0;
+    });
+    replication.replicate(&server.data).unwrap();
+
+    // The entity is built before the second packet updates it, so it's only activated once.
+    assert_eq!(receiver.receive(&mut client).unwrap(), 2);
+    client.update();
+    assert_eq!(client.systems.tracker.activated, 1);
+    assert_eq!(client.systems.tracker.reactivated, 1);
+    let local_player = receiver.local_entity(&player).unwrap();
+    assert_eq!(client.with_entity_data(&local_player, |e, c| c.position[e].x), Some(3.0));
+
+    // Updates to an entity the client removed itself are ignored.
+    client.remove_entity(local_player);
+    client.flush_queue();
+    server.modify_entity(player, |e: ModifyData<TestComponents>, c: &mut TestComponents| {
+        c.position[e].x = 4.0;
+    });
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    server.remove_entity(player);
+    server.flush_queue();
+    replication.replicate(&server.data).unwrap();
+    assert_eq!(receiver.receive(&mut client).unwrap(), 1);
+    client.flush_queue();
+    assert_eq!(client.entities().count(), 0);
+}
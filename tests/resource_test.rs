@@ -0,0 +1,75 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, DataHelper, EntityIter, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+/// How far everything moves each update.
+pub struct Speed(pub i32);
+
+/// How many updates have run.
+#[derive(Debug, PartialEq)]
+pub struct Frame(pub u32);
+
+components! {
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot] position: Position,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+            motion: EntitySystem<Motion> = EntitySystem::new(Motion, aspect!(<TestComponents> all: [position])),
+        },
+        passive: {
+        }
+    }
+}
+
+pub struct Motion;
+impl System for Motion { type Components = TestComponents; type Services = (); }
+impl EntityProcess for Motion
+{
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, ()>)
+    {
+        let speed = data.resources.get::<Speed>().map_or(0, |speed| speed.0);
+        for e in entities {
+            data.position[e].0 += speed;
+        }
+        data.resources.get_or_insert_with(|| Frame(0)).0 += 1;
+    }
+}
+
+#[test]
+fn test_resources()
+{
+    let mut world = World::<TestSystems>::new();
+    let entity = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+    });
+    assert!(world.resources.insert(Speed(2)).is_none());
+    world.update();
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.position[e]), Some(Position(2)));
+    assert_eq!(world.resources.get::<Frame>(), Some(&Frame(1)));
+
+    // Replacing a resource hands back the old one.
+    assert_eq!(world.resources.insert(Speed(5)).map(|speed| speed.0), Some(2));
+
+    // Restoring a snapshot doesn't touch resources.
+    let snapshot = world.snapshot();
+    world.update();
+    world.restore(&snapshot);
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.position[e]), Some(Position(2)));
+    assert_eq!(world.resources.get::<Frame>(), Some(&Frame(2)));
+
+    world.resources.get_mut::<Frame>().unwrap().0 = 10;
+    assert_eq!(world.resources.remove::<Frame>(), Some(Frame(10)));
+    assert!(!world.resources.contains::<Frame>());
+    assert_eq!(world.resources.len(), 1);
+}
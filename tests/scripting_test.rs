@@ -0,0 +1,195 @@
+#![cfg(feature="scripting")]
+
+#[macro_use]
+extern crate ecs;
+
+use std::collections::HashMap;
+
+use ecs::{BuildData, Entity, Process, World};
+use ecs::scripting::{ScriptApi, ScriptEngine, ScriptError, ScriptRegistry, ScriptSystem, ScriptValue, Scriptable};
+
+components! {
+    struct TestComponents {
+        #[hot] position: i32,
+        #[hot] velocity: i32,
+        #[cold] name: String,
+    }
+}
+
+systems! {
+    struct TestSystems<TestComponents, ()> {
+        active: {
+        },
+        passive: {
+        }
+    }
+}
+
+type Function = fn(&mut ScriptApi<TestComponents, ()>, &[ScriptValue]) -> Result<ScriptValue, ScriptError>;
+
+/// Stands in for a real language, with its "scripts" written as Rust functions.
+#[derive(Default)]
+pub struct MockEngine
+{
+    functions: HashMap<&'static str, Function>,
+}
+
+impl ScriptEngine<TestComponents, ()> for MockEngine
+{
+    type Error = ScriptError;
+    fn call(&mut self, function: &str, args: &[ScriptValue], api: &mut ScriptApi<TestComponents, ()>)
+        -> Result<ScriptValue, ScriptError>
+    {
+        match self.functions.get(function) {
+            Some(f) => f(api, args),
+            None => Ok(ScriptValue::Nil),
+        }
+    }
+}
+
+fn update(api: &mut ScriptApi<TestComponents, ()>, _: &[ScriptValue]) -> Result<ScriptValue, ScriptError>
+{
+    for entity in api.query("movers")? {
+        let position = api.get(entity, "position")?;
+        let velocity = api.get(entity, "velocity")?;
+        if let (ScriptValue::Int(p), ScriptValue::Int(v)) = (position, velocity) {
+            api.set(entity, "position", ScriptValue::Int(p + v))?;
+        }
+    }
+    Ok(ScriptValue::Nil)
+}
+
+fn spawn(api: &mut ScriptApi<TestComponents, ()>, args: &[ScriptValue]) -> Result<ScriptValue, ScriptError>
+{
+    let entity = api.create_entity();
+    api.set(entity, "name", args[0].clone())?;
+    api.set(entity, "position", ScriptValue::Int(0))?;
+    Ok(ScriptValue::Entity(entity))
+}
+
+fn broken(api: &mut ScriptApi<TestComponents, ()>, _: &[ScriptValue]) -> Result<ScriptValue, ScriptError>
+{
+    let entity = api.create_entity();
+    api.get(entity, "health")
+}
+
+#[test]
+fn test_script_system()
+{
+    let mut engine = MockEngine::default();
+    engine.functions.insert("update", update);
+    engine.functions.insert("spawn", spawn);
+    engine.functions.insert("broken", broken);
+
+    let mut registry = ScriptRegistry::new();
+    registry.register("position", |c: &TestComponents| &c.position, |c| &mut c.position);
+    registry.register("velocity", |c: &TestComponents| &c.velocity, |c| &mut c.velocity);
+    registry.register("name", |c: &TestComponents| &c.name, |c| &mut c.name);
+    registry.aspect("movers", aspect!(<TestComponents> all: [position, velocity]));
+
+    let mut world = World::<TestSystems>::new();
+    let mover = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, 1);
+        c.velocity.add(&e, 2);
+    });
+    assert!(world.add_system("scripts", ScriptSystem::new(engine, registry, &["update"])).is_ok());
+    world.update();
+    world.update();
+    assert_eq!(world.with_entity_data(&mover, |e, c| c.position[e]), Some(5));
+}
+
+#[test]
+fn test_script_api()
+{
+    let mut engine = MockEngine::default();
+    engine.functions.insert("spawn", spawn);
+    engine.functions.insert("broken", broken);
+    let mut registry = ScriptRegistry::new();
+    registry.register("position", |c: &TestComponents| &c.position, |c| &mut c.position);
+    registry.register("name", |c: &TestComponents| &c.name, |c| &mut c.name);
+    registry.aspect("named", aspect!(<TestComponents> all: [name]));
+    let mut scripts = ScriptSystem::new(engine, registry, &["broken"]);
+
+    let mut world = World::<TestSystems>::new();
+    let spawned = scripts.call("spawn", &[ScriptValue::Str("crate".to_string())], &mut world.data);
+    let entity = match spawned {
+        Ok(ScriptValue::Entity(entity)) => entity,
+        other => panic!("unexpected {:?}", other),
+    };
+    assert_eq!(world.with_entity_data(&entity, |e, c| c.name.get(&e)), Some(Some("crate".to_string())));
+
+    let mut api = ScriptApi::new(&mut world.data, &scripts.registry);
+    assert_eq!(api.get(entity, "position"), Ok(ScriptValue::Int(0)));
+    assert_eq!(api.set(entity, "position", ScriptValue::Str("up".to_string())),
+        Err(ScriptError::WrongType { component: "position".to_string(), value: ScriptValue::Str("up".to_string()) }));
+    assert_eq!(api.get(entity, "health"), Err(ScriptError::UnknownComponent("health".to_string())));
+    assert_eq!(api.query("movers"), Err(ScriptError::UnknownAspect("movers".to_string())));
+    assert_eq!(api.remove(entity, "position"), Ok(true));
+    assert_eq!(api.get(entity, "position"), Ok(ScriptValue::Nil));
+
+    // Queries list entities in order of id, however the world happens to store them.
+    let mut named: Vec<Entity> = (0..10).map(|_| {
+        let entity = api.create_entity();
+        api.set(entity, "name", ScriptValue::Str("rock".to_string())).unwrap();
+        entity
+    }).collect();
+    named.push(entity);
+    named.sort();
+    assert_eq!(api.query("named"), Ok(named));
+
+    assert_eq!(api.remove_entity(entity), Ok(()));
+
+    // Errors from functions run each update are kept for later.
+    scripts.process(&mut world.data);
+    scripts.process(&mut world.data);
+    let errors = scripts.take_errors();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], ("broken".to_string(), ScriptError::UnknownComponent("health".to_string())));
+    assert!(scripts.take_errors().is_empty());
+}
+
+#[test]
+fn test_scriptable_integers()
+{
+    assert_eq!(u32::from_script(&ScriptValue::Int(7)), Some(7));
+    assert_eq!(u32::from_script(&ScriptValue::Int(-1)), None);
+    assert_eq!(u32::from_script(&ScriptValue::Int(1 << 32)), None);
+    assert_eq!(i32::from_script(&ScriptValue::Int(-7)), Some(-7));
+    assert_eq!(i32::from_script(&ScriptValue::Int(i64::from(i32::MAX) + 1)), None);
+    assert_eq!(i32::from_script(&ScriptValue::Int(i64::from(i32::MIN) - 1)), None);
+    assert_eq!(i64::from_script(&ScriptValue::Int(i64::MIN)), Some(i64::MIN));
+
+    let mut registry = ScriptRegistry::new();
+    registry.register("position", |c: &TestComponents| &c.position, |c| &mut c.position);
+    let mut world = World::<TestSystems>::new();
+    let entity = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, 1);
+    });
+    let mut api = ScriptApi::new(&mut world.data, &registry);
+    assert_eq!(api.set(entity, "position", ScriptValue::Int(1 << 40)),
+        Err(ScriptError::WrongType { component: "position".to_string(), value: ScriptValue::Int(1 << 40) }));
+    assert_eq!(api.get(entity, "position"), Ok(ScriptValue::Int(1)));
+}
+
+#[test]
+fn test_script_changes_reactivate()
+{
+    let mut registry = ScriptRegistry::new();
+    registry.register("velocity", |c: &TestComponents| &c.velocity, |c| &mut c.velocity);
+
+    let mut world = World::<TestSystems>::new();
+    assert!(world.add_entity_group("movers", aspect!(<TestComponents> all: [position, velocity])).is_ok());
+    let entity = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, 1);
+    });
+    world.flush_queue();
+    assert!(!world.data.entity_group("movers").unwrap().contains(&entity));
+
+    assert_eq!(ScriptApi::new(&mut world.data, &registry).set(entity, "velocity", ScriptValue::Int(2)), Ok(()));
+    world.flush_queue();
+    assert!(world.data.entity_group("movers").unwrap().contains(&entity));
+
+    assert_eq!(ScriptApi::new(&mut world.data, &registry).remove(entity, "velocity"), Ok(true));
+    world.flush_queue();
+    assert!(!world.data.entity_group("movers").unwrap().contains(&entity));
+}
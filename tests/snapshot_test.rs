@@ -0,0 +1,96 @@
+
+#[macro_use]
+extern crate ecs;
+
+use ecs::{BuildData, DataHelper, EntityData, EntityIter, SnapshotBuffer, System, World};
+use ecs::system::{EntityProcess, EntitySystem};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position(pub i32);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Velocity(pub i32);
+
+components! {
+    #[derive(Clone)]
+    struct TestComponents {
+        #[hot] position: Position,
+        #[cold] velocity: Velocity,
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TestServices {
+    pub frame: u32,
+}
+
+impl ecs::ServiceManager for TestServices {}
+
+systems! {
+    struct TestSystems<TestComponents, TestServices> {
+        active: {
+            motion: EntitySystem<Motion> = EntitySystem::new(Motion(0), aspect!(<TestComponents> all: [position, velocity])),
+        },
+        passive: {
+        }
+    }
+}
+
+pub struct Motion(pub usize);
+impl System for Motion {
+    type Components = TestComponents;
+    type Services = TestServices;
+    fn activated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut TestServices) {
+        self.0 += 1;
+    }
+    fn deactivated(&mut self, _: &EntityData<TestComponents>, _: &TestComponents, _: &mut TestServices) {
+        self.0 -= 1;
+    }
+}
+impl EntityProcess for Motion {
+    fn process(&mut self, entities: EntityIter<TestComponents>, data: &mut DataHelper<TestComponents, TestServices>) {
+        data.services.frame += 1;
+        for e in entities {
+            let velocity = data.velocity[e];
+            data.position[e].0 += velocity.0;
+        }
+    }
+}
+
+#[test]
+fn test_snapshot_rollback() {
+    let mut world = World::<TestSystems>::new();
+    let mut history = SnapshotBuffer::new(3);
+
+    let mover = world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.velocity.add(&e, Velocity(2));
+    });
+    world.flush_queue();
+
+    for _ in 0..5 {
+        history.push(world.snapshot());
+        world.update();
+    }
+    assert_eq!(history.len(), 3);
+    assert_eq!(world.with_entity_data(&mover, |e, c| c.position[e]), Some(Position(10)));
+
+    // Spawn something that shouldn't survive the rollback, and leave it in the queue.
+    world.create_entity(|e: BuildData<TestComponents>, c: &mut TestComponents| {
+        c.position.add(&e, Position(0));
+        c.velocity.add(&e, Velocity(1));
+    });
+
+    let snapshot = history.rewind(1).unwrap().clone();
+    assert_eq!(history.len(), 2);
+    world.restore(&snapshot);
+
+    assert_eq!(world.services.frame, 3);
+    assert_eq!(world.with_entity_data(&mover, |e, c| c.position[e]), Some(Position(6)));
+    assert_eq!(world.systems.motion.0, 1);
+
+    world.update();
+    assert_eq!(world.with_entity_data(&mover, |e, c| c.position[e]), Some(Position(8)));
+    assert_eq!(world.entities().count(), 1);
+    assert!(history.get(2).is_none());
+}